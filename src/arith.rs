@@ -0,0 +1,210 @@
+// Arithmetic on runtime values, integers stay integers of their width and floats floats of theirs.
+use std::io;
+use crate::ast::{ BinOp, UnaryOp };
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+// What happens when an integer result does not fit its type, picked with --overflow.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    Trap,     // Stop with an error, the default.
+    Wrap,     // Keep the low bits, two's complement.
+    Saturate, // Clamp to the smallest or largest value of the type.
+}
+
+impl Overflow {
+    pub fn from_str(s: &str) -> Option<Overflow> {
+        return match s {
+            "trap"     => Some(Overflow::Trap),
+            "wrap"     => Some(Overflow::Wrap),
+            "saturate" => Some(Overflow::Saturate),
+            _          => None,
+        };
+    }
+}
+
+impl<'a> Evaluator<'a> {
+
+    pub fn arith(&self, op: BinOp, l: &Value, r: &Value, loc: &Location) -> Result<Value, io::Error> {
+        let lt = l.get_type();
+        let rt = r.get_type();
+
+        if lt.is_int() && rt.is_int() {
+            let t = match Type::meet_int(&lt, &rt) {
+                Some(t) => t,
+                None => {
+                    let err = format!("no integer type holds both {} and {}, {} {} {} needs operands of the same sign, cast one of them with as", lt, rt, lt, op, rt);
+                    return Err(self.error_at(loc, &err));
+                }
+            };
+
+            // Both are unsigned when they meet at u128, everything else fits in i128.
+            if t == Type::U128 {
+                if let (Some(a), Some(b)) = (l.as_u128(), r.as_u128()) {
+                    return self.uint_arith(op, a, b, loc);
+                }
+            }
+
+            if let (Some(a), Some(b)) = (l.as_i128(), r.as_i128()) {
+                return self.int_arith(op, a, b, &t, loc);
+            }
+        }
+
+        if let (Some(a), Some(b)) = (l.as_f64(), r.as_f64()) {
+            // An int meets a float as that float, f32 and f64 meet at f64.
+            let t = if lt == Type::F64 || rt == Type::F64 { Type::F64 } else { Type::F32 };
+            return self.float_arith(op, a, b, &t, loc);
+        }
+
+        // + joins two strings.
+        if let (Value::Str(a), Value::Str(b), BinOp::Add) = (l, r, op) {
+            return Ok(Value::Str(format!("{}{}", a, b)));
+        }
+
+        let err = format!("unsupported operand types {} {} {}", lt, op, rt);
+        return Err(self.error_at(loc, &err));
+    }
+
+    // The result is computed on i128 then fitted into t under the overflow policy.
+    pub fn int_arith(&self, op: BinOp, a: i128, b: i128, t: &Type, loc: &Location) -> Result<Value, io::Error> {
+        if (op == BinOp::Div || op == BinOp::Rem) && b == 0 {
+            return Err(self.error_at(loc, "Division by zero Error."));
+        }
+
+        // exact is None when even i128 overflowed, up tells which way it went.
+        let (exact, wrapped, up): (Option<i128>, i128, bool) = match op {
+            BinOp::Add => (a.checked_add(b), a.wrapping_add(b), b > 0),
+            BinOp::Sub => (a.checked_sub(b), a.wrapping_sub(b), b < 0),
+            BinOp::Mul => (a.checked_mul(b), a.wrapping_mul(b), (a < 0) == (b < 0)),
+            // Integer division truncates toward zero, the remainder keeps the sign of a.
+            BinOp::Div => (a.checked_div(b), a.wrapping_div(b), true),
+            BinOp::Rem => (Some(a.wrapping_rem(b)), a.wrapping_rem(b), true),
+            BinOp::Pow => {
+                let e = match u32::try_from(b) {
+                    Ok(e) => e,
+                    Err(_) if b < 0 => return Err(self.error_at(loc, "integer exponents can not be negative")),
+                    Err(_) => return Err(self.error_at(loc, "integer exponent is too large")),
+                };
+
+                (a.checked_pow(e), a.wrapping_pow(e), a >= 0 || e % 2 == 0)
+            },
+            _ => unreachable!("{} is not arithmetic", op),
+        };
+
+        return self.fit_int(exact, wrapped, up, t, loc, || format!("{} {} {}", a, op, b));
+    }
+
+    pub fn fit_int<F: Fn() -> String>(&self, exact: Option<i128>, wrapped: i128, up: bool, t: &Type, loc: &Location, describe: F) -> Result<Value, io::Error> {
+        if let Some(v) = exact.and_then(|v| Value::int_from_i128(v, t)) {
+            return Ok(v);
+        }
+
+        let v = match self.overflow {
+            Overflow::Trap => {
+                let err = format!("integer overflow, {} does not fit in {}", describe(), t);
+                return Err(self.error_at(loc, &err));
+            },
+            Overflow::Wrap => Value::int_wrapping(wrapped, t),
+            Overflow::Saturate => {
+                let v = match exact {
+                    Some(v) => v.clamp(t.int_min(), t.int_max()),
+                    None if up => t.int_max(),
+                    None => t.int_min(),
+                };
+
+                Value::int_from_i128(v, t)
+            },
+        };
+
+        match v {
+            Some(v) => return Ok(v),
+            None    => unreachable!("{} is not an integer type", t),
+        }
+    }
+
+    // u128 is the one type past i128, it is computed on u128 directly.
+    pub fn uint_arith(&self, op: BinOp, a: u128, b: u128, loc: &Location) -> Result<Value, io::Error> {
+        if (op == BinOp::Div || op == BinOp::Rem) && b == 0 {
+            return Err(self.error_at(loc, "Division by zero Error."));
+        }
+
+        let (exact, wrapped, up): (Option<u128>, u128, bool) = match op {
+            BinOp::Add => (a.checked_add(b), a.wrapping_add(b), true),
+            BinOp::Sub => (a.checked_sub(b), a.wrapping_sub(b), false),
+            BinOp::Mul => (a.checked_mul(b), a.wrapping_mul(b), true),
+            BinOp::Div => (Some(a / b), a / b, true),
+            BinOp::Rem => (Some(a % b), a % b, true),
+            BinOp::Pow => {
+                let e = match u32::try_from(b) {
+                    Ok(e)  => e,
+                    Err(_) => return Err(self.error_at(loc, "integer exponent is too large")),
+                };
+
+                (a.checked_pow(e), a.wrapping_pow(e), true)
+            },
+            _ => unreachable!("{} is not arithmetic", op),
+        };
+
+        return self.fit_uint(exact, wrapped, up, loc, || format!("{} {} {}", a, op, b));
+    }
+
+    pub fn fit_uint<F: Fn() -> String>(&self, exact: Option<u128>, wrapped: u128, up: bool, loc: &Location, describe: F) -> Result<Value, io::Error> {
+        if let Some(v) = exact {
+            return Ok(Value::U128(v));
+        }
+
+        match self.overflow {
+            Overflow::Trap => {
+                let err = format!("integer overflow, {} does not fit in {}", describe(), Type::U128);
+                return Err(self.error_at(loc, &err));
+            },
+            Overflow::Wrap     => return Ok(Value::U128(wrapped)),
+            Overflow::Saturate => return Ok(Value::U128(if up { u128::MAX } else { 0 })),
+        }
+    }
+
+    // The result is computed on f64 then rounded to t.
+    pub fn float_arith(&self, op: BinOp, a: f64, b: f64, t: &Type, loc: &Location) -> Result<Value, io::Error> {
+        let v: f64 = match op {
+            BinOp::Add => a + b,
+            BinOp::Sub => a - b,
+            BinOp::Mul => a * b,
+            BinOp::Div | BinOp::Rem => {
+                if b == 0.0 {
+                    return Err(self.error_at(loc, "Division by zero Error."));
+                }
+
+                if op == BinOp::Div { a / b } else { a % b }
+            },
+            BinOp::Pow => a.powf(b),
+            _ => unreachable!("{} is not arithmetic", op),
+        };
+
+        match Value::float_from_f64(v, t) {
+            Some(v) => return Ok(v),
+            None    => unreachable!("{} is not a float type", t),
+        }
+    }
+
+    pub fn negate(&self, v: &Value, loc: &Location) -> Result<Value, io::Error> {
+        if let Value::U128(u) = v {
+            let exact = if *u == 0 { Some(0) } else { None };
+            return self.fit_uint(exact, u.wrapping_neg(), false, loc, || format!("{}({})", UnaryOp::Neg, u));
+        }
+
+        if let Some(i) = v.as_i128() {
+            return self.fit_int(i.checked_neg(), i.wrapping_neg(), true, &v.get_type(), loc, || format!("{}({})", UnaryOp::Neg, i));
+        }
+
+        match v {
+            Value::F32(f) => return Ok(Value::F32(-f)),
+            Value::F64(f) => return Ok(Value::F64(-f)),
+            _             => {},
+        }
+
+        let err = format!("unsupported operand type {}{}", UnaryOp::Neg, v.get_type());
+        return Err(self.error_at(loc, &err));
+    }
+}
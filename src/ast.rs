@@ -0,0 +1,340 @@
+// AST.
+// The parser turns the token stream into these nodes, the evaluator walks them.
+use std::fmt;
+use crate::enums::TokenT;
+use crate::token::Location;
+use crate::types::Type;
+
+#[derive(Debug)]
+pub struct Program {
+    pub stmts: Vec<Stmt>,
+}
+
+#[derive(Debug)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub loc:  Location,
+}
+
+#[derive(Debug)]
+pub enum StmtKind {
+    // define name -> T [= expr] / define name = expr, the type is then the type of expr.
+    // const name [-> T] = expr is a define that never changes.
+    Define {
+        name:          String,
+        declared_type: Option<Type>,
+        init:          Option<Expr>,
+        constant:      bool,
+    },
+    // name = expr
+    Assign {
+        name:  String,
+        value: Expr,
+    },
+    // xs[i] = expr / p.x = expr, target is the Index or Field expression.
+    AssignTo {
+        target: Expr,
+        value:  Expr,
+    },
+    // if |cond| { ... } else { ... }, an else if is an else block holding a single If.
+    If {
+        cond:       Expr,
+        then_block: Block,
+        else_block: Option<Block>,
+    },
+    // while |cond| { ... }
+    While {
+        cond: Expr,
+        body: Block,
+    },
+    // for i in a..b [step s] { ... }, i is an int scoped to the body.
+    For {
+        var:   String,
+        range: Range,
+        body:  Block,
+    },
+    // for x in xs { ... }, x takes every element of the array or character of the string.
+    ForEach {
+        var:      String,
+        iterable: Expr,
+        body:     Block,
+    },
+    Break,
+    Continue,
+    // { ... } on its own, opens a new scope.
+    Block(Block),
+    // process name(a -> T, ...) [-> T] { ... }
+    Process(Proc),
+    // struct Name { a -> T, ... }
+    Struct(StructDecl),
+    // enum Name { A, B(T, ...), ... }
+    Enum(EnumDecl),
+    // match expr { pattern => expr or { ... }, ... }, its arms can be blocks here.
+    Match(Match),
+    // return [expr]
+    Return(Option<Expr>),
+    // write(...), or any other expression used for its side effects.
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub end:   Location, // The closing }.
+}
+
+#[derive(Debug)]
+pub struct Range {
+    pub start:     Expr,
+    pub end:       Expr,
+    pub inclusive: bool, // ..=
+    pub step:      Option<Expr>,
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub name:          String,
+    pub declared_type: Type,
+    pub loc:           Location,
+}
+
+#[derive(Debug)]
+pub struct Proc {
+    pub name:        String,
+    pub params:      Vec<Param>,
+    pub return_type: Option<Type>, // None when the process returns nothing.
+    pub body:        Block,
+    pub loc:         Location,
+}
+
+#[derive(Debug)]
+pub struct EnumDecl {
+    pub name:     String,
+    pub variants: Vec<Variant>,
+    pub loc:      Location,
+}
+
+#[derive(Debug)]
+pub struct Variant {
+    pub name:   String,
+    pub fields: Vec<Type>, // The types of its payload, empty for A.
+    pub loc:    Location,
+}
+
+#[derive(Debug)]
+pub struct Match {
+    pub subject: Expr,
+    pub arms:    Vec<Arm>,
+}
+
+#[derive(Debug)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub body:    ArmBody,
+    pub loc:     Location, // The start of the pattern.
+}
+
+#[derive(Debug)]
+pub enum Pattern {
+    // _
+    Wildcard,
+    // A / B(x, _), the names are bound to the payload for the body of the arm.
+    Variant {
+        name:     String,
+        bindings: Vec<String>,
+    },
+    // 1, "a", True, compared with ==.
+    Literal(Expr),
+}
+
+#[derive(Debug)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Block), // Only when the match is a statement, an expression needs a value from every arm.
+}
+
+#[derive(Debug)]
+pub struct StructDecl {
+    pub name:   String,
+    pub fields: Vec<Param>, // In the order they were declared, values print in it.
+    pub loc:    Location,
+}
+
+#[derive(Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub loc:  Location,
+}
+
+#[derive(Debug)]
+pub enum ExprKind {
+    Int(u128),
+    Float(f64),
+    Str(String),
+    // "a {x} b", its pieces are printed the way write prints them and joined.
+    Interp(Vec<Expr>),
+    // [a, b, c]
+    Array(Vec<Expr>),
+    // [v; n], n copies of v. n is known before the program runs.
+    Repeat {
+        value: Box<Expr>,
+        count: Box<Expr>,
+    },
+    // {k: v, ...}, the entries in the order they were written.
+    Map(Vec<(Expr, Expr)>),
+    // Name { a: expr, ... }
+    StructLit {
+        name:   String,
+        fields: Vec<(String, Expr)>,
+    },
+    Bool(bool),
+    Var(String),
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    Unary {
+        op:      UnaryOp,
+        operand: Box<Expr>,
+    },
+    // target[index], located at the [.
+    Index {
+        target: Box<Expr>,
+        index:  Box<Expr>,
+    },
+    // target[start..end] / target[start..=end], either bound can be left out.
+    Slice {
+        target:    Box<Expr>,
+        start:     Option<Box<Expr>>,
+        end:       Option<Box<Expr>>,
+        inclusive: bool,
+    },
+    // match expr { pattern => expr, ... }
+    Match(Box<Match>),
+    // target.name, located at the name.
+    Field {
+        target: Box<Expr>,
+        name:   String,
+    },
+    // expr as T, located at the as.
+    Cast {
+        operand: Box<Expr>,
+        target:  Type,
+    },
+    // The location of a binary expression is the location of its operator.
+    Binary {
+        op:  BinOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+    And,
+    Or,
+}
+
+impl Block {
+    // True when every path through the block ends in a return.
+    pub fn always_returns(&self) -> bool {
+        return self.stmts.iter().any(|stmt| stmt.always_returns());
+    }
+}
+
+impl Stmt {
+    pub fn always_returns(&self) -> bool {
+        match &self.kind {
+            StmtKind::Return(_)    => return true,
+            StmtKind::Block(block) => return block.always_returns(),
+            StmtKind::If { then_block, else_block: Some(else_block), .. } => {
+                return then_block.always_returns() && else_block.always_returns();
+            },
+            // A match that runs takes one of its arms, it is an error when none fits.
+            StmtKind::Match(m) => return m.arms.iter().all(|arm| matches!(&arm.body, ArmBody::Block(b) if b.always_returns())),
+            _ => return false,
+        }
+    }
+}
+
+impl BinOp {
+    pub fn is_comparison(&self) -> bool {
+        return matches!(self, BinOp::Eq | BinOp::NotEq | BinOp::Lt | BinOp::Gt | BinOp::LtEq | BinOp::GtEq);
+    }
+
+    pub fn is_logical(&self) -> bool {
+        return matches!(self, BinOp::And | BinOp::Or);
+    }
+
+    pub fn from_token(t: TokenT) -> Option<BinOp> {
+        return match t {
+            TokenT::PLUS__        => Some(BinOp::Add),
+            TokenT::MINUS__       => Some(BinOp::Sub),
+            TokenT::MULT__        => Some(BinOp::Mul),
+            TokenT::DIV__         => Some(BinOp::Div),
+            TokenT::MOD__         => Some(BinOp::Rem),
+            TokenT::POW__         => Some(BinOp::Pow),
+            TokenT::COMP_EQ__     => Some(BinOp::Eq),
+            TokenT::COMP_NOT_EQ__ => Some(BinOp::NotEq),
+            TokenT::LT__          => Some(BinOp::Lt),
+            TokenT::GT__          => Some(BinOp::Gt),
+            TokenT::COMP_LT_EQ__  => Some(BinOp::LtEq),
+            TokenT::COMP_GT_EQ__  => Some(BinOp::GtEq),
+            TokenT::AND__         => Some(BinOp::And),
+            TokenT::OR__          => Some(BinOp::Or),
+            _                     => None,
+        };
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match *self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+        };
+
+        return write!(f, "{}", printable)
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match *self {
+            BinOp::Add   => "+",
+            BinOp::Sub   => "-",
+            BinOp::Mul   => "*",
+            BinOp::Div   => "/",
+            BinOp::Rem   => "%",
+            BinOp::Pow   => "^",
+            BinOp::Eq    => "==",
+            BinOp::NotEq => "!=",
+            BinOp::Lt    => "<",
+            BinOp::Gt    => ">",
+            BinOp::LtEq  => "<=",
+            BinOp::GtEq  => ">=",
+            BinOp::And   => "&&",
+            BinOp::Or    => "||",
+        };
+
+        return write!(f, "{}", printable)
+    }
+}
@@ -0,0 +1,173 @@
+// Built-in processes, write, the string library, push / pop and the map functions.
+use std::io;
+use crate::ast::Expr;
+use crate::enums::*;
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+impl<'a> Evaluator<'a> {
+
+    pub fn call_builtin(&mut self, name: &str, args: &[Expr], loc: &Location) -> Result<Option<Value>, io::Error> {
+        if name == WRITE || name == WRITELN {
+            self.write(args, name == WRITELN)?;
+            return Ok(None);
+        }
+
+        if name == PUSH || name == POP {
+            return self.push_pop(name, args, loc);
+        }
+
+        if name == REMOVE {
+            return self.remove(args, loc);
+        }
+
+        let arity = match name {
+            LEN | UPPER | LOWER | TRIM       => 1,
+            KEYS | VALUES                    => 1,
+            SPLIT | CONTAINS | FIND | REPEAT => 2,
+            REPLACE                          => 3,
+            _ => unreachable!("{} is not a built-in", name),
+        };
+
+        if args.len() != arity {
+            let err = format!("{} expects {} arguments but got {}", name, arity, args.len());
+            return Err(self.error_at(loc, &err));
+        }
+
+        // The first argument is read where it lives, len(xs) does not copy xs.
+        let first = self.resolve_read(&args[0])?;
+        let mut rest: Vec<Value> = Vec::new();
+
+        for arg in &args[1..] {
+            rest.push(self.eval(arg)?);
+        }
+
+        let values: Vec<&Value> = std::iter::once(self.source_ref(&first)?).chain(&rest).collect();
+
+        let result = match name {
+            LEN => {
+                match (values[0], Self::positions(values[0])) {
+                    (Value::Map(_, _, entries), _) => self.int_result(entries.len(), loc)?,
+                    (_, Some((len, _)))            => self.int_result(len, loc)?,
+                    _ => return Err(self.arg_error(name, args, &values, 0, "a string, an array or a map")),
+                }
+            },
+            KEYS | VALUES => {
+                // In the order the keys were inserted.
+                match values[0] {
+                    Value::Map(k, _, entries) if name == KEYS => Value::Array(k.clone(), entries.iter().map(|(k, _)| k.clone()).collect()),
+                    Value::Map(_, v, entries)                 => Value::Array(v.clone(), entries.iter().map(|(_, v)| v.clone()).collect()),
+                    _ => return Err(self.arg_error(name, args, &values, 0, "a map")),
+                }
+            },
+            UPPER    => Value::Str(self.str_arg(name, args, &values, 0)?.to_uppercase()),
+            LOWER    => Value::Str(self.str_arg(name, args, &values, 0)?.to_lowercase()),
+            TRIM     => Value::Str(self.str_arg(name, args, &values, 0)?.trim().to_string()),
+            // An array contains its elements, a map its keys and a string its substrings.
+            CONTAINS if matches!(values[0], Value::Array(..) | Value::Map(..)) => {
+                let t = match values[0].get_type() {
+                    Type::Array(t) | Type::Map(t, _) => *t,
+                    _ => unreachable!(),
+                };
+
+                let needle = self.coerce(values[1].clone(), &t, &args[1].loc)?;
+
+                match values[0] {
+                    Value::Array(_, items)    => Value::Bool(items.iter().any(|item| item.equals(&needle))),
+                    Value::Map(_, _, entries) => Value::Bool(entries.get(&needle).is_some()),
+                    _ => unreachable!(),
+                }
+            },
+            CONTAINS => {
+                let s   = self.str_arg(name, args, &values, 0)?;
+                let sub = self.str_arg(name, args, &values, 1)?;
+                Value::Bool(s.contains(sub))
+            },
+            REPLACE => {
+                let s    = self.str_arg(name, args, &values, 0)?;
+                let from = self.str_arg(name, args, &values, 1)?;
+                let to   = self.str_arg(name, args, &values, 2)?;
+                Value::Str(s.replace(from, to))
+            },
+            FIND => {
+                // The character position of the first match, -1 when there is none.
+                let s   = self.str_arg(name, args, &values, 0)?;
+                let sub = self.str_arg(name, args, &values, 1)?;
+
+                match s.find(sub) {
+                    Some(byte) => self.int_result(s[..byte].chars().count(), loc)?,
+                    None       => Value::I32(-1),
+                }
+            },
+            SPLIT => {
+                let s   = self.str_arg(name, args, &values, 0)?;
+                let sep = self.str_arg(name, args, &values, 1)?;
+
+                if sep.is_empty() {
+                    return Err(self.error_at(&args[1].loc, "split needs a separator that is not empty"));
+                }
+
+                let pieces = s.split(sep).map(|p| Value::Str(p.to_string())).collect();
+                Value::Array(Type::Str, pieces)
+            },
+            REPEAT => {
+                let s = self.str_arg(name, args, &values, 0)?;
+                let n = match values[1].as_i128() {
+                    Some(n) => n,
+                    None    => return Err(self.arg_error(name, args, &values, 1, INT)),
+                };
+
+                if n < 0 {
+                    return Err(self.error_at(&args[1].loc, "repeat can not repeat a string a negative number of times"));
+                }
+
+                // Anything past 4GB is a runaway count, not a string anyone wants.
+                let len = usize::try_from(n).ok().and_then(|n| n.checked_mul(s.len()));
+
+                match len {
+                    Some(len) if len <= u32::MAX as usize => Value::Str(s.repeat(n as usize)),
+                    _ => return Err(self.error_at(loc, "repeat would make a string that is too long")),
+                }
+            },
+            _ => unreachable!("{} is not a built-in", name),
+        };
+
+        return Ok(Some(result));
+    }
+
+    // write(a, b, ...) prints its arguments one after the other, writeln ends the line.
+    pub fn write(&mut self, args: &[Expr], newline: bool) -> Result<(), io::Error> {
+        // Everything is evaluated first, an error does not leave half a line behind.
+        let mut out = self.format(args)?;
+
+        if newline {
+            out.push('\n');
+        }
+
+        print!("{}", out);
+        return Ok(());
+    }
+
+    pub fn str_arg<'v>(&self, name: &str, args: &[Expr], values: &[&'v Value], i: usize) -> Result<&'v str, io::Error> {
+        if let Value::Str(s) = values[i] {
+            return Ok(s);
+        }
+
+        return Err(self.arg_error(name, args, values, i, STRING));
+    }
+
+    pub fn arg_error(&self, name: &str, args: &[Expr], values: &[&Value], i: usize, expected: &str) -> io::Error {
+        let err = format!("argument {} of {} expects {} but found {}", i + 1, name, expected, values[i].get_type());
+        return self.error_at(&args[i].loc, &err);
+    }
+
+    // Lengths and positions are ints.
+    pub fn int_result(&self, n: usize, loc: &Location) -> Result<Value, io::Error> {
+        match i32::try_from(n) {
+            Ok(n)  => return Ok(Value::I32(n)),
+            Err(_) => return Err(self.error_at(loc, &format!("{} does not fit in {}", n, Type::I32))),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+// Casts, expr as T. A value that does not survive the conversion is an error at the as.
+use std::io;
+use crate::enums::{ BOOL_TRUE, BOOL_FALSE };
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+impl<'a> Evaluator<'a> {
+
+    pub fn cast(&self, value: Value, target: &Type, loc: &Location) -> Result<Value, io::Error> {
+        let from = value.get_type();
+
+        if from == *target {
+            return Ok(value);
+        }
+
+        if let Value::Str(s) = &value {
+            return self.parse_as(s, target, loc);
+        }
+
+        let cast: Option<Value> = match target {
+            Type::Str => Some(Value::Str(value.to_string())),
+            t if t.is_int() => {
+                match &value {
+                    Value::Bool(b)                => Value::int_from_i128(*b as i128, t),
+                    Value::F32(_) | Value::F64(_) => value.as_f64().and_then(|f| Self::float_to_int(f, t)),
+                    _ if from.is_int()            => value.as_i128().and_then(|i| Value::int_from_i128(i, t)),
+                    _ => return Err(self.cannot_cast(&from, target, loc)),
+                }
+            },
+            t if t.is_float() => {
+                match value.as_f64() {
+                    // Rounding is fine, running out of range is not.
+                    Some(f) if *t == Type::F32 && f.is_finite() && (f as f32).is_infinite() => None,
+                    Some(f) => Value::float_from_f64(f, t),
+                    None    => return Err(self.cannot_cast(&from, target, loc)),
+                }
+            },
+            _ => return Err(self.cannot_cast(&from, target, loc)),
+        };
+
+        match cast {
+            Some(v) => return Ok(v),
+            None => {
+                let err = format!("{} does not fit in {}", value, target);
+                return Err(self.error_at(loc, &err));
+            }
+        }
+    }
+
+    // A float loses its fraction toward zero, it must still be in range of t.
+    pub fn float_to_int(f: f64, t: &Type) -> Option<Value> {
+        if !f.is_finite() {
+            return None;
+        }
+
+        let f     = f.trunc();
+        let limit = 2f64.powi(t.bits() as i32);
+
+        let in_range = if t.is_unsigned() { f >= 0.0 && f < limit } else { f >= -limit / 2.0 && f < limit / 2.0 };
+
+        if !in_range {
+            return None;
+        }
+
+        if *t == Type::U128 {
+            return Some(Value::U128(f as u128));
+        }
+
+        return Value::int_from_i128(f as i128, t);
+    }
+
+    // True when an integer comes back unchanged from the float it was turned into.
+    pub fn holds_exactly(int: &Value, float: &Value) -> bool {
+        let f = match float.as_f64() {
+            Some(f) if f.is_finite() => f,
+            _                        => return false,
+        };
+
+        match int {
+            Value::U128(u) => return f < 2f64.powi(128) && f as u128 == *u,
+            _              => return f < 2f64.powi(127) && int.as_i128() == Some(f as i128),
+        }
+    }
+
+    pub fn parse_as(&self, s: &str, target: &Type, loc: &Location) -> Result<Value, io::Error> {
+        let parsed: Option<Value> = match target {
+            Type::U128                    => s.parse::<u128>().ok().map(Value::U128),
+            t if t.is_int()               => s.parse::<i128>().ok().and_then(|i| Value::int_from_i128(i, t)),
+            t if t.is_float()             => s.parse::<f64>().ok().and_then(|f| Value::float_from_f64(f, t)),
+            Type::Bool if s == BOOL_TRUE  => Some(Value::Bool(true)),
+            Type::Bool if s == BOOL_FALSE => Some(Value::Bool(false)),
+            Type::Bool                    => None,
+            _ => return Err(self.cannot_cast(&Type::Str, target, loc)),
+        };
+
+        match parsed {
+            Some(v) => return Ok(v),
+            None => {
+                let err = format!("can not parse \"{}\" as {}", s, target);
+                return Err(self.error_at(loc, &err));
+            }
+        }
+    }
+
+    pub fn cannot_cast(&self, from: &Type, target: &Type, loc: &Location) -> io::Error {
+        let err = format!("can not cast {} to {}", from, target);
+        return self.error_at(loc, &err);
+    }
+}
@@ -1,31 +1,64 @@
-/*
-use crate::stack::Stack;
+// Conditions: the pipe delimited boolean expression of an if or a while -> |x == 10|
 use std::io;
+use crate::ast::*;
+use crate::enums::TokenT;
+use crate::kasper_parser::KasperParser;
 
-// Parses -> |x == 10|
-pub struct condition {
-    pub stack: &Stack,
-    pub value: String,
-    pub eval:  bool,
-}
+impl<'a> KasperParser<'a> {
 
-impl condition {
-    
-    pub fn new(stack: &Stack) -> Self {
-        Self {
-            stack: stack,
-            value: String::new(),
-            eval:  false,
-        }
+    pub fn parse_condition(&mut self) -> Result<Expr, io::Error> {
+        self.expect(TokenT::PIPE__, "a pipe | to open the condition")?;
+        let cond = self.parse_expression()?;
+        self.expect(TokenT::PIPE__, "a pipe | to close the condition")?;
+
+        return Ok(cond);
     }
 
-    pub fn evaluate(&mut self) -> Result<(), io::Error>{
-        if self.value.len() > 0 {
-            
-            return Err(err);
+    pub fn parse_branching(&mut self) -> Result<Stmt, io::Error> {
+        // if |cond| { ... } else if |cond| { ... } else { ... }
+        let token      = self.expect(TokenT::IF__, "if")?;
+        let cond       = self.parse_condition()?;
+        let then_block = self.parse_block()?;
+        let mut else_block: Option<Block> = None;
+
+        // The else may sit on the line after the closing curly.
+        let mut offset = 0;
+        while self.peek_at(offset).token_type == TokenT::NL__ {
+            offset += 1;
         }
 
+        if self.peek_at(offset).token_type == TokenT::ELSE__ {
+            for _ in 0..offset {
+                self.advance();
+            }
+
+            self.advance(); // else
+
+            if self.peek_type() == TokenT::IF__ {
+                let nested = self.parse_branching()?;
+                let end    = self.prev_loc();
+                else_block = Some(Block { stmts: vec![nested], end });
+            } else {
+                else_block = Some(self.parse_block()?);
+            }
+        }
+
+        return Ok(Stmt {
+            kind: StmtKind::If { cond, then_block, else_block },
+            loc: token.loc,
+        });
     }
-}
 
-*/
+    pub fn parse_while(&mut self) -> Result<Stmt, io::Error> {
+        // while |cond| { ... }
+        let token = self.expect(TokenT::WHILE__, "while")?;
+        let cond  = self.parse_condition()?;
+
+        let body  = self.parse_loop_body()?;
+
+        return Ok(Stmt {
+            kind: StmtKind::While { cond, body },
+            loc: token.loc,
+        });
+    }
+}
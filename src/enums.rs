@@ -6,20 +6,6 @@
 use std::fmt;
 use std::collections::HashMap;
 
-/*
-    
-    pub const UMAX_8_BIT:   u8    = 0xFF;
-    pub const UMAX_16_BIT:  u16   = 0xFFFF;
-    pub const UMAX_32_BIT:  u32   = 0xFFFFFFFF;
-    pub const UMAX_64_BIT:  u64   = 0xFFFFFFFFFFFFFFFF;
-    pub const UMAX_128_BIT: u128  = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF;
-    pub const MAX_8_BIT:   i8    = 0x7F; // 127
-    pub const MAX_16_BIT:  i16   = 0x7FFF;
-    pub const MAX_32_BIT:  i32   = 0x7FFFFFFF;
-    pub const MAX_64_BIT:  i64   = 0x7FFFFFFFFFFFFFFF;
-    pub const MAX_128_BIT: i128  = 0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFF; // >> Division by two.
-
-*/
 #[allow(dead_code)]
 pub const DQUOTE:         char  = '\"';
 pub const SQUOTE:         char  = '\'';
@@ -32,15 +18,19 @@ pub const OPAR:           char  = '(';
 pub const CPAR:           char  = ')';
 pub const OCURLY:         char  = '{';
 pub const CCURLY:         char  = '}';
+pub const OBRACKET:       char  = '[';
+pub const CBRACKET:       char  = ']';
 
 pub const PLUS:           char  = '+';
 pub const MULT:           char  = '*';
 pub const MINUS:          char  = '-';
 pub const DIV:            char  = '/';
+pub const MOD:            char  = '%';
 pub const POW:            char  = '^';
 
 pub const DOT:            char  = '.';
 pub const COMA:           char  = ',';
+pub const COLON:          char  = ':';
 pub const SEMICOLON:      char  = ';';
 pub const EQUAL:          char  = '=';
 pub const GT:             char  = '>';
@@ -48,32 +38,85 @@ pub const LT:             char  = '<';
 pub const QM:             char  = '!';
 pub const PIPE:           char  = '|';
 pub const ESCAPE:         char  = '\\';
+pub const UNDERSCORE:     char  = '_';
 
-pub const _THIN_ARROW:     &str  = "->"; 
-pub const _FAT_ARROW:      &str  = "=>"; 
+pub const THIN_ARROW:     &str  = "->"; 
+pub const FAT_ARROW:      &str  = "=>"; 
 
 // Comparison operators.
 pub const COMP_EQ:        &str  = "==";
-pub const COMP_NOT_EQ:    &str  = "=!";
+pub const COMP_NOT_EQ:    &str  = "!=";
 pub const COMP_LT_EQ:     &str  = "<=";
 pub const COMP_GT_EQ:     &str  = ">=";
 
+// Ranges.
+pub const RANGE:          &str  = ".."; // ..= is a RANGE followed by =
+
+// Logical operators.
+pub const AND:            &str  = "&&";
+pub const OR:             &str  = "||";
+
 // TYPLES:
 pub const STRING:     &str  = "string"; 
-pub const INT:        &str  = "int";
+pub const INT:        &str  = "int"; // i32
+pub const INT_8:      &str  = "i8";
+pub const INT_16:     &str  = "i16";
+pub const INT_32:     &str  = "i32";
 pub const INT_64:     &str  = "i64";
+pub const INT_128:    &str  = "i128";
+pub const UINT_8:     &str  = "u8";
+pub const UINT_16:    &str  = "u16";
+pub const UINT_32:    &str  = "u32";
+pub const UINT_64:    &str  = "u64";
+pub const UINT_128:   &str  = "u128";
 pub const BOOL:       &str  = "bool";
+pub const MAP:        &str  = "map"; // map[K, V]
+pub const FLOAT:      &str  = "float"; // f64
+pub const FLOAT_64:   &str  = "f64";
+pub const FLOAT_32:   &str  = "f32";
 
 // Key words
 pub const WRITE:      &str  = "write";
+pub const WRITELN:    &str  = "writeln";
+pub const LEN:        &str  = "len";
+pub const UPPER:      &str  = "upper";
+pub const LOWER:      &str  = "lower";
+pub const TRIM:       &str  = "trim";
+pub const SPLIT:      &str  = "split";
+pub const CONTAINS:   &str  = "contains";
+pub const REPLACE:    &str  = "replace";
+pub const FIND:       &str  = "find";
+pub const REPEAT:     &str  = "repeat";
+pub const PUSH:       &str  = "push";
+pub const POP:        &str  = "pop";
+pub const REMOVE:     &str  = "remove";
+pub const KEYS:       &str  = "keys";
+pub const VALUES:     &str  = "values";
 pub const DEFINE:     &str  = "define";
-pub const _PROCC:      &str  = "process";
+pub const CONST:      &str  = "const";
+pub const PROCC:       &str  = "process";
+pub const RETURN:      &str  = "return";
+pub const WHILE:       &str  = "while";
+pub const BREAK:       &str  = "break";
+pub const CONTINUE:    &str  = "continue";
+pub const FOR:         &str  = "for";
+pub const IN:          &str  = "in";
+pub const STEP:        &str  = "step";
+pub const AS:          &str  = "as";
+pub const STRUCT:      &str  = "struct";
+pub const ENUM:        &str  = "enum";
+pub const MATCH:       &str  = "match";
+pub const WILDCARD:    &str  = "_"; // The match arm that takes anything.
+pub const MAX_REPEAT:  i128  = 1 << 24; // The most elements [v; n] makes, past it the count is a runaway.
+
+// Processes that come with the language, they can not be redefined.
+pub const BUILTINS: [&str; 16] = [WRITE, WRITELN, LEN, UPPER, LOWER, TRIM, SPLIT, CONTAINS, REPLACE, FIND, REPEAT, PUSH, POP, REMOVE, KEYS, VALUES];
 pub const BOOL_TRUE:  &str  = "True";
 pub const BOOL_FALSE: &str  = "False";
 pub const IF:          &str  = "if";
 pub const ELSE:        &str  = "else";
 
-#[derive(Copy, Clone, PartialEq, Hash, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Hash, Eq)]
 #[allow(non_camel_case_types, dead_code)]
 pub enum TokenT {
     NL__,
@@ -87,10 +130,15 @@ pub enum TokenT {
     CPAR__,
     OCURLY__,
     CCURLY__,
+    OBRACKET__,
+    CBRACKET__,
     PLUS__,
     MINUS__,
     MULT__,
+    MOD__,
     COMA__,
+    COLON__,
+    DOT__,
     SEMICOLON__,
     EQUAL__,
     GT__,
@@ -105,6 +153,8 @@ pub enum TokenT {
     NUMBER__,
         
     STRING__,
+    INTERP_BEGIN__, // "a {x} b" is INTERP_BEGIN__ STRING__ { x } STRING__ INTERP_END__
+    INTERP_END__,
     VARNAME__,
     
     // Comparison operators
@@ -113,6 +163,14 @@ pub enum TokenT {
     COMP_LT_EQ__,
     COMP_GT_EQ__,
 
+    // Logical operators
+    AND__,
+    OR__,
+
+    // Ranges
+    RANGE__,
+    RANGE_INCL__,
+
 
     // primitives
     BOOL_TRUE__,
@@ -121,9 +179,20 @@ pub enum TokenT {
     // Types
     FLOAT__,
     INT_T,
+    INT_T_8,
+    INT_T_16,
     INT_T_64,
+    INT_T_128,
+    UINT_T_8,
+    UINT_T_16,
+    UINT_T_32,
+    UINT_T_64,
+    UINT_T_128,
     STRING_T,
     BOOL_T,
+    MAP_T,
+    FLOAT_T,
+    FLOAT_T_32,
     
     // Built-ins + funcs..
     FUNC_CALL__,
@@ -134,6 +203,18 @@ pub enum TokenT {
     KEY_WORD_PROCESS__,
     ELSE__,
     IF__,
+    RETURN__,
+    WHILE__,
+    BREAK__,
+    CONTINUE__,
+    FOR__,
+    IN__,
+    STEP__,
+    AS__,
+    STRUCT__,
+    ENUM__,
+    MATCH__,
+    CONST__,
 }
 
 impl fmt::Display for TokenT {
@@ -147,15 +228,21 @@ impl fmt::Display for TokenT {
             TokenT::CPAR__             => "CPAR__",
             TokenT::OCURLY__           => "OCURLY__",
             TokenT::CCURLY__           => "CCURLY__",
+            TokenT::OBRACKET__         => "OBRACKET__",
+            TokenT::CBRACKET__         => "CBRACKET__",
             TokenT::PLUS__             => "PLUS__",
             TokenT::MINUS__            => "MINUS__",
             TokenT::COMA__             => "COMA__",
+            TokenT::COLON__            => "COLON__",
+            TokenT::DOT__              => "DOT__",
             TokenT::SEMICOLON__        => "SEMICOLON__",
             TokenT::EQUAL__            => "EQUAL__",
             TokenT::GT__               => "GT__",
             TokenT::LT__               => "LT__",
             TokenT::NUMBER__           => "NUMBER__",
             TokenT::STRING__           => "STRING__",
+            TokenT::INTERP_BEGIN__     => "INTERP_BEGIN__",
+            TokenT::INTERP_END__       => "INTERP_END__",
             TokenT::QM__               => "QM__",
             TokenT::VARNAME__          => "VARNAME__",
             TokenT::DIV__              => "DIV__",
@@ -168,18 +255,46 @@ impl fmt::Display for TokenT {
             TokenT::FUNC_CALL__        => "FUNC_CALL__",
             TokenT::STRING_T           => "STRING_T",
             TokenT::INT_T              => "INT_T",
+            TokenT::INT_T_8            => "INT_T_8",
+            TokenT::INT_T_16           => "INT_T_16",
             TokenT::INT_T_64           => "INT_64",
+            TokenT::INT_T_128          => "INT_T_128",
+            TokenT::UINT_T_8           => "UINT_T_8",
+            TokenT::UINT_T_16          => "UINT_T_16",
+            TokenT::UINT_T_32          => "UINT_T_32",
+            TokenT::UINT_T_64          => "UINT_T_64",
+            TokenT::UINT_T_128         => "UINT_T_128",
             TokenT::FLOAT__            => "FLOAT__",
             TokenT::MULT__             => "MULT__",
+            TokenT::MOD__              => "MOD__",
             TokenT::BOOL_T             => "BOOL_T",
+            TokenT::MAP_T              => "MAP_T",
+            TokenT::FLOAT_T            => "FLOAT_T",
+            TokenT::FLOAT_T_32         => "FLOAT_T_32",
             TokenT::BOOL_TRUE__        => "BOOL_TRUE__",
             TokenT::BOOL_FALSE__       => "BOOL_FALSE_",
             TokenT::IF__               => "IF__",
             TokenT::ELSE__             => "ELSE__",
+            TokenT::RETURN__           => "RETURN__",
+            TokenT::WHILE__            => "WHILE__",
+            TokenT::BREAK__            => "BREAK__",
+            TokenT::CONTINUE__         => "CONTINUE__",
+            TokenT::FOR__              => "FOR__",
+            TokenT::IN__               => "IN__",
+            TokenT::STEP__             => "STEP__",
+            TokenT::AS__               => "AS__",
+            TokenT::STRUCT__           => "STRUCT__",
+            TokenT::ENUM__             => "ENUM__",
+            TokenT::MATCH__            => "MATCH__",
+            TokenT::CONST__            => "CONST__",
+            TokenT::RANGE__            => "RANGE__",
+            TokenT::RANGE_INCL__       => "RANGE_INCL__",
             TokenT::COMP_EQ__          => "COMP_EQ__", 
             TokenT::COMP_NOT_EQ__      => "COMP_NOT_EQ__", 
             TokenT::COMP_LT_EQ__       => "COMP_LT_EQ__", 
             TokenT::COMP_GT_EQ__       => "COMP_GT_EQ__", 
+            TokenT::AND__              => "AND__",
+            TokenT::OR__               => "OR__",
             TokenT::PIPE__             => "PIPE__",      
             TokenT::POW__              => "POW__,",
         }; 
@@ -198,16 +313,21 @@ pub fn make_token_table() -> HashMap<char, TokenT> {
     map.insert(CPAR,      TokenT::CPAR__);
     map.insert(OCURLY,    TokenT::OCURLY__);
     map.insert(CCURLY,    TokenT::CCURLY__);
+    map.insert(OBRACKET,  TokenT::OBRACKET__);
+    map.insert(CBRACKET,  TokenT::CBRACKET__);
     
     map.insert(PLUS,      TokenT::PLUS__);
     map.insert(MINUS,     TokenT::MINUS__);
     map.insert(MULT,      TokenT::MULT__);
+    map.insert(MOD,       TokenT::MOD__);
     map.insert(DIV,       TokenT::DIV__);
     map.insert(POW,       TokenT::POW__);
     map.insert(NL,        TokenT::NL__);
 
 
     map.insert(COMA,      TokenT::COMA__);
+    map.insert(COLON,     TokenT::COLON__);
+    map.insert(DOT,       TokenT::DOT__);
     map.insert(SEMICOLON, TokenT::SEMICOLON__);
     map.insert(EQUAL,     TokenT::EQUAL__);
     map.insert(GT,        TokenT::GT__);
@@ -220,15 +340,41 @@ pub fn make_token_table() -> HashMap<char, TokenT> {
 }
 
 
-pub fn make_prec_table() -> HashMap<String, i32> {
+// Tokens made of two chars, they are matched before the single char table.
+pub fn make_double_token_table() -> HashMap<&'static str, TokenT> {
+    let mut map: HashMap<&'static str, TokenT> = HashMap::new();
+
+    map.insert(THIN_ARROW,  TokenT::THIN_ARROW__);
+    map.insert(FAT_ARROW,   TokenT::FAT_ARROW__);
+    map.insert(COMP_EQ,     TokenT::COMP_EQ__);
+    map.insert(COMP_NOT_EQ, TokenT::COMP_NOT_EQ__);
+    map.insert(COMP_LT_EQ,  TokenT::COMP_LT_EQ__);
+    map.insert(COMP_GT_EQ,  TokenT::COMP_GT_EQ__);
+    map.insert(AND,         TokenT::AND__);
+    map.insert(OR,          TokenT::OR__);
+    map.insert(RANGE,       TokenT::RANGE__);
+
+    map
+}
+
+pub fn make_prec_table() -> HashMap<TokenT, i32> {
     
-    let mut map: HashMap<String, i32> = HashMap::new();
+    let mut map: HashMap<TokenT, i32> = HashMap::new();
     // Adding all the keys.  
-    map.insert(POW.to_string(), 4);    
-    map.insert(MULT.to_string(), 3);
-    map.insert(DIV.to_string(),  3);
-    map.insert(PLUS.to_string(), 2);
-    map.insert(MINUS.to_string(), 2);
+    map.insert(TokenT::POW__,         7);    
+    map.insert(TokenT::MULT__,        6);
+    map.insert(TokenT::DIV__,         6);
+    map.insert(TokenT::MOD__,         6);
+    map.insert(TokenT::PLUS__,        5);
+    map.insert(TokenT::MINUS__,       5);
+    map.insert(TokenT::LT__,          4);
+    map.insert(TokenT::GT__,          4);
+    map.insert(TokenT::COMP_LT_EQ__,  4);
+    map.insert(TokenT::COMP_GT_EQ__,  4);
+    map.insert(TokenT::COMP_EQ__,     3);
+    map.insert(TokenT::COMP_NOT_EQ__, 3);
+    map.insert(TokenT::AND__,         2);
+    map.insert(TokenT::OR__,          1);
     // Return the map.  
     map
 }
@@ -0,0 +1,694 @@
+// Evaluator, walks the Program produced by the parser and executes it.
+use std::io;
+use std::collections::HashMap;
+use crate::ast::*;
+use crate::arith::Overflow;
+use crate::enums::BUILTINS;
+use crate::stack::{ Expired, Stack, Variable };
+use crate::typeck::TypeChecker;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+use crate::util::make_error_at;
+
+// Deep enough for real recursion, shallow enough to fail before the STACK_SIZE stack main runs the program on does.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+// What a statement tells the block that runs it.
+pub enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>, Location),
+}
+
+pub struct Evaluator<'a> {
+    pub file_path: &'a str,
+    pub stack:     Stack,                      // Stores variables...
+    pub procs:     HashMap<String, &'a Proc>,  // Every declared process...
+    pub structs:   HashMap<String, &'a StructDecl>, // Every declared struct...
+    pub enums:     HashMap<String, &'a EnumDecl>,   // Every declared enum...
+    pub variants:  HashMap<String, &'a EnumDecl>,   // The enum of every variant...
+    depth:         usize,                      // Calls in flight...
+    current:       Option<&'a Proc>,           // The running process...
+    pub overflow:  Overflow,                   // What integer overflow does...
+    pub warn_shadow: bool,                     // Whether a name hiding one of an outer scope is reported...
+}
+
+impl<'a> Evaluator<'a> {
+
+    pub fn new(path: &'a str) -> Self {
+        Self {
+            file_path: path,
+            stack: Stack::new(),
+            procs: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            variants: HashMap::new(),
+            depth: 0,
+            current: None,
+            overflow: Overflow::Trap,
+            warn_shadow: false,
+        }
+    }
+
+    pub fn error_at(&self, loc: &Location, text: &str) -> io::Error {
+        return make_error_at(self.file_path, loc, text);
+    }
+
+    // The error for a name that can not be found, it tells when the name went out of scope.
+    pub fn undefined(&self, name: &str, loc: &Location) -> io::Error {
+        return self.not_defined(name, self.stack.get_expired(name), loc);
+    }
+
+    pub fn not_defined(&self, name: &str, expired: Option<&Expired>, loc: &Location) -> io::Error {
+        if let Some(expired) = expired {
+            let err = format!("{} is not defined in this scope, it was defined at {}:{}:{} in a block that ended at {}:{}:{}",
+                              name,
+                              self.file_path,
+                              expired.loc.row,
+                              expired.loc.col,
+                              self.file_path,
+                              expired.end.row,
+                              expired.end.col
+                        );
+
+            return self.error_at(loc, &err);
+        }
+
+        let err = format!("{} is not defined try define {} -> T", name, name);
+        return self.error_at(loc, &err);
+    }
+
+    pub fn lookup(&self, name: &str, loc: &Location) -> Result<&Variable, io::Error> {
+        match self.stack.get(name) {
+            Some(var) => return Ok(var),
+            None      => return Err(self.undefined(name, loc)),
+        }
+    }
+
+    pub fn run(&mut self, program: &'a Program) -> Result<(), io::Error> {
+        // Processes, structs and enums are known before anything runs, so they can be used above their declaration.
+        for stmt in &program.stmts {
+            if let StmtKind::Process(proc) = &stmt.kind {
+                if let Some(other) = self.procs.get(&proc.name) {
+                    let err = format!("process {} is already defined at {}:{}:{}", proc.name, self.file_path, other.loc.row, other.loc.col);
+                    return Err(self.error_at(&proc.loc, &err));
+                }
+
+                self.procs.insert(proc.name.clone(), proc);
+            }
+
+            if let StmtKind::Struct(decl) = &stmt.kind {
+                self.check_new_type(&decl.name, &decl.loc)?;
+                self.structs.insert(decl.name.clone(), decl);
+            }
+
+            if let StmtKind::Enum(decl) = &stmt.kind {
+                self.check_new_type(&decl.name, &decl.loc)?;
+
+                for variant in &decl.variants {
+                    if let Some(other) = self.variants.get(&variant.name) {
+                        let err = format!("{} is already a variant of {} at {}:{}:{}", variant.name, other.name, self.file_path, other.loc.row, other.loc.col);
+                        return Err(self.error_at(&variant.loc, &err));
+                    }
+
+                    self.variants.insert(variant.name.clone(), decl);
+                }
+
+                self.enums.insert(decl.name.clone(), decl);
+            }
+        }
+
+        self.check_declarations()?;
+
+        // Every type error in the file is reported before the first line runs.
+        TypeChecker::new(self).check(program)?;
+
+        for stmt in &program.stmts {
+            self.exec_stmt(stmt)?;
+        }
+
+        return Ok(());
+    }
+
+    pub fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Flow, io::Error> {
+        match &stmt.kind {
+            StmtKind::Define { name, declared_type, init, .. } => self.define_var(name, declared_type, init, &stmt.loc)?,
+            StmtKind::Assign { name, value }              => self.assign_var(name, value, &stmt.loc)?,
+            StmtKind::AssignTo { target, value }          => self.assign_to(target, value)?,
+            StmtKind::Expr(expr)                          => self.exec_expr(expr)?,
+            StmtKind::Block(block)                        => return self.exec_block(block),
+            StmtKind::Process(_) | StmtKind::Struct(_) | StmtKind::Enum(_) => {}, // Registered by run.
+            StmtKind::Match(m)                            => return self.exec_match(m, &stmt.loc),
+            StmtKind::Break                               => return Ok(Flow::Break),
+            StmtKind::Continue                            => return Ok(Flow::Continue),
+            StmtKind::While { cond, body } => {
+                while self.eval_bool(cond)? {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Next | Flow::Continue => continue,
+                        flow => return Ok(flow), // return
+                    }
+                }
+            },
+            StmtKind::For { var, range, body } => return self.exec_for(var, range, body, &stmt.loc),
+            StmtKind::ForEach { var, iterable, body } => return self.exec_foreach(var, iterable, body, &stmt.loc),
+            StmtKind::Return(value) => {
+                let hint = self.current.and_then(|proc| proc.return_type.as_ref());
+                let v = match value {
+                    Some(expr) => Some(self.eval_with(expr, hint)?),
+                    None       => None,
+                };
+
+                return Ok(Flow::Return(v, stmt.loc));
+            },
+            StmtKind::If { cond, then_block, else_block } => {
+                // Only the taken branch is executed.
+                if self.eval_bool(cond)? {
+                    return self.exec_block(then_block);
+                }
+
+                if let Some(block) = else_block {
+                    return self.exec_block(block);
+                }
+            },
+        }
+
+        return Ok(Flow::Next);
+    }
+
+    // Every block gets its own scope, what it defines is dropped at its }.
+    pub fn exec_block(&mut self, block: &Block) -> Result<Flow, io::Error> {
+        self.stack.push_scope();
+
+        for stmt in &block.stmts {
+            match self.exec_stmt(stmt) {
+                Ok(Flow::Next) => continue,
+                res => {
+                    self.stack.pop_scope(block.end);
+                    return res;
+                },
+            }
+        }
+
+        self.stack.pop_scope(block.end);
+        return Ok(Flow::Next);
+    }
+
+    pub fn eval_bound(&mut self, expr: &Expr) -> Result<i64, io::Error> {
+        let v = self.eval_with(expr, Some(&Type::I32))?;
+        match self.coerce(v, &Type::I32, &expr.loc)? {
+            Value::I32(v) => return Ok(v as i64),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn exec_for(&mut self, var: &str, range: &Range, body: &Block, loc: &Location) -> Result<Flow, io::Error> {
+        // The bounds and the step are evaluated once, before the first iteration.
+        let start = self.eval_bound(&range.start)?;
+        let end   = self.eval_bound(&range.end)?;
+        let step  = match &range.step {
+            Some(expr) => self.eval_bound(expr)?,
+            None       => 1,
+        };
+
+        if step == 0 {
+            let loc = if let Some(expr) = &range.step { expr.loc } else { *loc };
+            return Err(self.error_at(&loc, "the step of a for loop can not be zero"));
+        }
+
+        let mut i = start;
+
+        loop {
+            let in_range = match (step > 0, range.inclusive) {
+                (true,  false) => i < end,
+                (true,  true)  => i <= end,
+                (false, false) => i > end,
+                (false, true)  => i >= end,
+            };
+
+            if !in_range {
+                break;
+            }
+
+            // The loop variable lives in a scope around the body, a fresh one every iteration.
+            self.stack.push_scope();
+            self.stack.define(var, Type::I32, Value::I32(i as i32), *loc);
+            let flow = self.exec_block(body);
+            self.stack.pop_scope(body.end);
+
+            match flow? {
+                Flow::Break => break,
+                Flow::Next | Flow::Continue => {},
+                flow => return Ok(flow), // return
+            }
+
+            i += step;
+        }
+
+        return Ok(Flow::Next);
+    }
+
+    pub fn exec_foreach(&mut self, var: &str, iterable: &Expr, body: &Block, loc: &Location) -> Result<Flow, io::Error> {
+        // The elements are taken before the first iteration, changing the array in the body does not change the loop.
+        // A map gives its keys in the order they were inserted.
+        let items: Vec<Value> = match self.eval(iterable)? {
+            Value::Array(_, items) => items,
+            Value::Map(_, _, entries) => entries.into_iter().map(|(k, _)| k).collect(),
+            Value::Str(s)          => s.chars().map(|c| Value::Str(c.to_string())).collect(),
+            v => {
+                let err = format!("can not iterate over {}, expected an array, a map, a string or a range", v.get_type());
+                return Err(self.error_at(&iterable.loc, &err));
+            }
+        };
+
+        for item in items {
+            self.stack.push_scope();
+            self.stack.define(var, item.get_type(), item, *loc);
+            let flow = self.exec_block(body);
+            self.stack.pop_scope(body.end);
+
+            match flow? {
+                Flow::Break => break,
+                Flow::Next | Flow::Continue => {},
+                flow => return Ok(flow), // return
+            }
+        }
+
+        return Ok(Flow::Next);
+    }
+
+    // Fits a value into the declared type, integers must fit its width.
+    // An int becomes a float on its own, a float never silently loses its fraction or its precision.
+    pub fn coerce(&self, value: Value, declared_type: &Type, loc: &Location) -> Result<Value, io::Error> {
+        let found = value.get_type();
+
+        if found == *declared_type {
+            return Ok(value);
+        }
+
+        if declared_type.is_int() {
+            if found.is_int() {
+                // A u128 past i128 only fits in a u128, and that was the same type.
+                match value.as_i128().and_then(|i| Value::int_from_i128(i, declared_type)) {
+                    Some(v) => return Ok(v),
+                    None => {
+                        let err = format!("{} does not fit in {}", value, declared_type);
+                        return Err(self.error_at(loc, &err));
+                    }
+                }
+            }
+
+            if found.is_float() {
+                let err = format!("expected {} but found {}, a {} does not turn into an integer on its own, cast it with as", declared_type, found, found);
+                return Err(self.error_at(loc, &err));
+            }
+        }
+
+        // Any int and f32 widen to f64, f64 to f32 would round. So would an int the float can not hold, rounding takes an as.
+        if declared_type.is_float() && found != Type::F64 {
+            if let Some(v) = value.as_f64().and_then(|f| Value::float_from_f64(f, declared_type)) {
+                if found.is_int() && !Self::holds_exactly(&value, &v) {
+                    let err = format!("{} as {} would be rounded to {}, cast it with as to round it", value, declared_type, v);
+                    return Err(self.error_at(loc, &err));
+                }
+
+                return Ok(v);
+            }
+        }
+
+        let err = format!("expected {} but found {}", declared_type, found);
+        return Err(self.error_at(loc, &err));
+    }
+
+    pub fn define_var(&mut self, name: &str, declared_type: &Option<Type>, init: &Option<Expr>, loc: &Location) -> Result<(), io::Error> {
+
+        let (declared_type, value) = match (declared_type, init) {
+            (Some(t), None) => {
+                self.check_type(t, loc)?;
+                (t.clone(), self.default_value(t, loc)?)
+            },
+            (Some(t), Some(expr)) => {
+                self.check_type(t, loc)?;
+                let v = self.eval_with(expr, Some(t))?;
+                (t.clone(), self.coerce(v, t, &expr.loc)?)
+            },
+            // define x = expr, x keeps the type of its first value.
+            (None, Some(expr)) => {
+                let v = self.eval(expr)?;
+                (v.get_type(), v)
+            },
+            (None, None) => unreachable!("the parser asks for a type or a value"),
+        };
+
+        self.stack.define(name, declared_type, value, *loc);
+        return Ok(());
+    }
+
+    pub fn assign_var(&mut self, name: &str, value: &Expr, loc: &Location) -> Result<(), io::Error> {
+
+        let declared_type = self.lookup(name, loc)?.declared_type.clone();
+
+        let v     = self.eval_with(value, Some(&declared_type))?;
+        let found = v.get_type();
+
+        let v = match self.coerce(v, &declared_type, &value.loc) {
+            Ok(v)  => v,
+            Err(_) => {
+                let err = format!("can not assign {} to {}, it was defined as {}", found, name, declared_type);
+                return Err(self.error_at(&value.loc, &err));
+            }
+        };
+
+        if let Some(var) = self.stack.get_mut(name) {
+            var.value = v;
+        }
+
+        return Ok(());
+    }
+
+    pub fn exec_expr(&mut self, expr: &Expr) -> Result<(), io::Error> {
+        if let ExprKind::Call { name, args } = &expr.kind {
+            self.call(name, args, &expr.loc)?;
+            return Ok(());
+        }
+
+        // Any other expression is evaluated for its errors and dropped.
+        self.eval(expr)?;
+        return Ok(());
+    }
+
+    pub fn call(&mut self, name: &str, args: &[Expr], loc: &Location) -> Result<Option<Value>, io::Error> {
+        if BUILTINS.contains(&name) {
+            return self.call_builtin(name, args, loc);
+        }
+
+        let proc: &'a Proc = match self.procs.get(name) {
+            Some(proc) => proc,
+            None if self.variants.contains_key(name) => return Ok(Some(self.construct(name, args, loc)?)),
+            None => {
+                let err = format!("{} is not defined", name);
+                return Err(self.error_at(loc, &err));
+            }
+        };
+
+        if args.len() != proc.params.len() {
+            let err = format!("{} expects {} arguments but got {}", name, proc.params.len(), args.len());
+            return Err(self.error_at(loc, &err));
+        }
+
+        // The arguments are evaluated in the scope of the caller.
+        let mut values: Vec<Value> = Vec::new();
+
+        for (i, (arg, param)) in args.iter().zip(&proc.params).enumerate() {
+            let v     = self.eval_with(arg, Some(&param.declared_type))?;
+            let found = v.get_type();
+
+            match self.coerce(v, &param.declared_type, &arg.loc) {
+                Ok(v)  => values.push(v),
+                Err(_) => {
+                    let err = format!("argument {} of {} expects {} but found {}", i + 1, name, param.declared_type, found);
+                    return Err(self.error_at(&arg.loc, &err));
+                }
+            }
+        }
+
+        if self.depth >= MAX_CALL_DEPTH {
+            let err = format!("stack overflow, {} calls deep while calling {}", self.depth, name);
+            return Err(self.error_at(loc, &err));
+        }
+
+        self.depth += 1;
+        let saved  = self.stack.push_frame();
+        let caller = self.current.replace(proc);
+
+        for (param, value) in proc.params.iter().zip(values) {
+            self.stack.define(&param.name, param.declared_type.clone(), value, param.loc);
+        }
+
+        let flow = self.exec_block(&proc.body);
+
+        self.stack.pop_frame(saved);
+        self.current = caller;
+        self.depth -= 1;
+
+        match (flow?, &proc.return_type) {
+            (Flow::Return(Some(v), ret_loc), Some(t)) => return Ok(Some(self.coerce(v, t, &ret_loc)?)),
+            (Flow::Return(None, _), None) | (Flow::Next, None) => return Ok(None),
+            (Flow::Return(Some(_), ret_loc), None) => {
+                let err = format!("{} does not return a value, remove the value or add -> T to its declaration", name);
+                return Err(self.error_at(&ret_loc, &err));
+            },
+            (Flow::Return(None, ret_loc), Some(t)) => {
+                let err = format!("{} must return {} {}", name, t.article(), t);
+                return Err(self.error_at(&ret_loc, &err));
+            },
+            (Flow::Next, Some(t)) => {
+                let err = format!("{} ended without returning {} {}", name, t.article(), t);
+                return Err(self.error_at(&proc.body.end, &err));
+            },
+            (Flow::Break, _) | (Flow::Continue, _) => unreachable!("the parser keeps break and continue inside loops"),
+        }
+    }
+
+    pub fn call_value(&mut self, name: &str, args: &[Expr], loc: &Location) -> Result<Value, io::Error> {
+        match self.call(name, args, loc)? {
+            Some(v) => return Ok(v),
+            None => {
+                let err = format!("{} does not return a value", name);
+                return Err(self.error_at(loc, &err));
+            }
+        }
+    }
+
+    // The text write prints for a list of expressions, one after the other.
+    pub fn format(&mut self, exprs: &[Expr]) -> Result<String, io::Error> {
+        let mut out = String::new();
+
+        for expr in exprs {
+            out.push_str(&self.eval(expr)?.to_string());
+        }
+
+        return Ok(out);
+    }
+
+    pub fn eval(&mut self, expr: &Expr) -> Result<Value, io::Error> {
+        return self.eval_with(expr, None);
+    }
+
+    // An integer literal, it takes the hinted integer type or the smallest of int, i64, i128 and u128 that holds it.
+    // A float hint is ignored, 7 / 2 is integer division even when it ends up in a float.
+    pub fn int_literal(&self, v: u128, negative: bool, hint: Option<&Type>, loc: &Location) -> Result<Value, io::Error> {
+        let text = if negative { format!("-{}", v) } else { v.to_string() };
+
+        // Past i128 only a u128 holds it.
+        if !negative && i128::try_from(v).is_err() {
+            match hint {
+                Some(t) if t.is_int() && *t != Type::U128 => return Err(self.error_at(loc, &format!("{} does not fit in {}", text, t))),
+                _ => return Ok(Value::U128(v)),
+            }
+        }
+
+        let v: i128 = match (i128::try_from(v), negative) {
+            (Ok(v), false) => v,
+            (Ok(v), true)  => -v,
+            (Err(_), true) if v == i128::MIN.unsigned_abs() => i128::MIN,
+            _ => return Err(self.error_at(loc, &format!("number literal {} is too large", text))),
+        };
+
+        match hint {
+            Some(t) if t.is_int() => {
+                match Value::int_from_i128(v, t) {
+                    Some(value) => return Ok(value),
+                    None => return Err(self.error_at(loc, &format!("{} does not fit in {}", text, t))),
+                }
+            },
+            _ => {},
+        }
+
+        for t in [Type::I32, Type::I64] {
+            if let Some(value) = Value::int_from_i128(v, &t) {
+                return Ok(value);
+            }
+        }
+
+        return Ok(Value::I128(v));
+    }
+
+    // hint is the type the value is headed for, untyped integer and float literals and array literals take it.
+    pub fn eval_with(&mut self, expr: &Expr, hint: Option<&Type>) -> Result<Value, io::Error> {
+        let hint = hint.filter(|t| t.is_number() || matches!(t, Type::Array(_) | Type::Map(..)));
+
+        match &expr.kind {
+            ExprKind::Int(v)    => return self.int_literal(*v, false, hint, &expr.loc),
+            ExprKind::Float(v)  => {
+                // A float literal is an f64 unless it is headed for an f32.
+                match hint {
+                    Some(Type::F32) => return Ok(Value::F32(*v as f32)),
+                    _               => return Ok(Value::F64(*v)),
+                }
+            },
+            ExprKind::Str(s)    => return Ok(Value::Str(s.clone())),
+            ExprKind::Interp(pieces) => return Ok(Value::Str(self.format(pieces)?)),
+            ExprKind::Array(elements) => return self.eval_array(elements, hint, &expr.loc),
+            ExprKind::Repeat { value, count } => return self.eval_repeat(value, count, hint),
+            ExprKind::Map(entries) => return self.eval_map(entries, hint, &expr.loc),
+            ExprKind::StructLit { name, fields } => return self.eval_struct(name, fields, &expr.loc),
+            ExprKind::Field { .. } | ExprKind::Index { .. } => return self.read(expr),
+            ExprKind::Bool(v)   => return Ok(Value::Bool(*v)),
+            ExprKind::Var(name) => {
+                // A variant without a payload is a value on its own, unless a variable took its name.
+                if self.stack.get(name).is_none() && self.variants.contains_key(name) {
+                    return self.construct(name, &[], &expr.loc);
+                }
+
+                return Ok(self.lookup(name, &expr.loc)?.value.clone());
+            },
+            ExprKind::Match(m) => return self.eval_match(m, hint, &expr.loc),
+            ExprKind::Call { name, args } => return self.call_value(name, args, &expr.loc),
+            ExprKind::Slice { target, start, end, inclusive } => {
+                let source = self.resolve_read(target)?;
+                let start = match start {
+                    Some(e) => Some(self.eval_position(e)?),
+                    None    => None,
+                };
+                let end = match end {
+                    Some(e) => Some(self.eval_position(e)?),
+                    None    => None,
+                };
+
+                return self.slice(self.source_ref(&source)?, start, end, *inclusive, &expr.loc);
+            },
+            ExprKind::Cast { operand, target } => {
+                let v = self.eval(operand)?;
+                return self.cast(v, target, &expr.loc);
+            },
+            ExprKind::Unary { op: UnaryOp::Not, .. } => return Ok(Value::Bool(self.eval_bool(expr)?)),
+            ExprKind::Binary { op, .. } if op.is_comparison() || op.is_logical() => return Ok(Value::Bool(self.eval_bool(expr)?)),
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                // -2147483648 is an int, 2147483648 alone is not.
+                if let ExprKind::Int(v) = operand.kind {
+                    return self.int_literal(v, true, hint, &expr.loc);
+                }
+
+                let v = self.eval_with(operand, hint)?;
+                return self.negate(&v, &expr.loc);
+            },
+            ExprKind::Binary { op, lhs, rhs } => {
+                let l = self.eval_with(lhs, hint)?;
+                let t = l.get_type();
+                let r = self.eval_with(rhs, hint.or(Some(&t)))?;
+
+                return self.arith(*op, &l, &r, &expr.loc);
+            },
+        }
+    }
+
+    pub fn eval_bool(&mut self, expr: &Expr) -> Result<bool, io::Error> {
+        match &expr.kind {
+            ExprKind::Bool(v) => return Ok(*v),
+            ExprKind::Var(name) => {
+                let var = self.lookup(name, &expr.loc)?;
+
+                if let Value::Bool(v) = var.value {
+                    return Ok(v);
+                }
+
+                let err = format!("{} is not a bool, it was defined as {}", name, var.declared_type);
+                return Err(self.error_at(&expr.loc, &err));
+            },
+            ExprKind::Call { name, args } => {
+                match self.call_value(name, args, &expr.loc)? {
+                    Value::Bool(v) => return Ok(v),
+                    v => {
+                        let err = format!("{} returns {}, expected a bool", name, v.get_type());
+                        return Err(self.error_at(&expr.loc, &err));
+                    }
+                }
+            },
+            ExprKind::Unary { op: UnaryOp::Not, operand } => return Ok(!self.eval_bool(operand)?),
+            ExprKind::Binary { op: BinOp::And, lhs, rhs } => {
+                // Short circuit, the rhs only runs when it can change the result.
+                return Ok(self.eval_bool(lhs)? && self.eval_bool(rhs)?);
+            },
+            ExprKind::Binary { op: BinOp::Or, lhs, rhs } => {
+                return Ok(self.eval_bool(lhs)? || self.eval_bool(rhs)?);
+            },
+            ExprKind::Binary { op, lhs, rhs } if op.is_comparison() => {
+                let l = self.eval(lhs)?;
+                let t = l.get_type();
+                let r = self.eval_with(rhs, Some(&t))?;
+                return self.compare(*op, &l, &r, &expr.loc);
+            },
+            _ => {
+                match self.eval(expr)? {
+                    Value::Bool(v) => return Ok(v),
+                    v => {
+                        let err = format!("expected a bool but found {}", v.get_type());
+                        return Err(self.error_at(&expr.loc, &err));
+                    }
+                }
+            },
+        }
+    }
+
+    // == and != work on any two values of the same kind, the ordering ones on numbers and strings.
+    // Arrays are equal when their elements are, maps when they hold the same entries in any order, structs when their fields are
+    // and enums when they are the same variant with equal payloads.
+    pub fn compare(&self, op: BinOp, l: &Value, r: &Value, loc: &Location) -> Result<bool, io::Error> {
+        let ordering = match (l, r) {
+            (Value::Str(a), Value::Str(b))   => a.partial_cmp(b),
+            (Value::Array(..), Value::Array(..)) | (Value::Map(..), Value::Map(..)) |
+            (Value::Struct(..), Value::Struct(..)) | (Value::Enum(..), Value::Enum(..)) if l.get_type() == r.get_type() => {
+                if op != BinOp::Eq && op != BinOp::NotEq {
+                    let kind = match l { Value::Map(..) => "maps", Value::Struct(..) => "structs", Value::Enum(..) => "enums", _ => "arrays" };
+                    return Err(self.error_at(loc, &format!("{} can not compare {}", op, kind)));
+                }
+
+                return Ok(l.equals(r) == (op == BinOp::Eq));
+            },
+            (Value::Bool(a), Value::Bool(b)) => {
+                if op != BinOp::Eq && op != BinOp::NotEq {
+                    return Err(self.error_at(loc, &format!("{} can not compare bools", op)));
+                }
+
+                a.partial_cmp(b)
+            },
+            _ if l.get_type().is_int() && r.get_type().is_int() => {
+                // Compared as i128, unless one is a u128 past it, then negatives are below it.
+                match (l.as_i128(), r.as_i128(), l.as_u128(), r.as_u128()) {
+                    (Some(a), Some(b), _, _) => a.partial_cmp(&b),
+                    (_, _, Some(a), Some(b)) => a.partial_cmp(&b),
+                    (_, _, None, _)          => Some(std::cmp::Ordering::Less),
+                    (_, _, _, None)          => Some(std::cmp::Ordering::Greater),
+                }
+            },
+            _ => {
+                match (l.as_f64(), r.as_f64()) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => {
+                        let err = format!("can not compare {} {} {}", l.get_type(), op, r.get_type());
+                        return Err(self.error_at(loc, &err));
+                    }
+                }
+            },
+        };
+
+        // NaN compares false with everything, but is not equal to itself either.
+        let ordering = match ordering {
+            Some(o) => o,
+            None    => return Ok(op == BinOp::NotEq),
+        };
+
+        match op {
+            BinOp::Eq    => return Ok(ordering.is_eq()),
+            BinOp::NotEq => return Ok(ordering.is_ne()),
+            BinOp::Lt    => return Ok(ordering.is_lt()),
+            BinOp::Gt    => return Ok(ordering.is_gt()),
+            BinOp::LtEq  => return Ok(ordering.is_le()),
+            BinOp::GtEq  => return Ok(ordering.is_ge()),
+            _ => unreachable!(),
+        }
+    }
+}
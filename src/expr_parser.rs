@@ -1,282 +1,350 @@
+// Expression parsing, precedence climbing over the prec table.
 use std::io;
+use crate::ast::*;
+use crate::enums::TokenT;
+use crate::kasper_parser::KasperParser;
+use crate::token::Location;
 
-use crate::lexer::{ KasperLexer, match_lexer_token };
-use crate::token::Token;
-use crate::util::make_error;
-use crate::enums::{ TokenT, make_prec_table };
-use std::collections::HashMap;
-use crate::stack::*;
+impl<'a> KasperParser<'a> {
 
+    pub fn parse_expression(&mut self) -> Result<Expr, io::Error> {
+        return self.parse_binary(0);
+    }
 
-pub struct ArithmaticParser {
-    pub iostack: Vec<Token>,
-    pub prec_table: HashMap<String, i32>
-}
+    pub fn parse_binary(&mut self, min_prec: i32) -> Result<Expr, io::Error> {
+        let mut lhs = self.parse_cast()?;
 
-fn is_parent(operand: TokenT) -> bool {
-    return operand == TokenT::OPAR__ || operand == TokenT::CPAR__;
-}
+        loop {
+            let token = self.peek().clone();
+
+            let prec = match self.prec_table.get(&token.token_type) {
+                Some(p) if *p >= min_prec => *p,
+                _ => break,
+            };
+
+            let op = match BinOp::from_token(token.token_type) {
+                Some(op) => op,
+                None => break,
+            };
 
-impl ArithmaticParser {
-    
-    pub fn new() -> Self {
-        ArithmaticParser { 
-            iostack: Vec::new(),
-            prec_table: make_prec_table(),
+            self.advance();
+
+            // ^ is right associative, everything else binds to the left.
+            let next_prec = if op == BinOp::Pow { prec } else { prec + 1 };
+            let rhs = self.parse_binary(next_prec)?;
+
+            lhs = Expr {
+                kind: ExprKind::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) },
+                loc: token.loc,
+            };
         }
+
+        return Ok(lhs);
     }
 
-    pub fn _dump_stack(&mut self) {
-        for i in 0..self.iostack.len() {
-            print!("{} ", self.iostack[i].value);
-            
+    // as binds tighter than any binary operator and looser than - and !, -x as i64 casts -x.
+    pub fn parse_cast(&mut self) -> Result<Expr, io::Error> {
+        let mut expr = self.parse_unary()?;
+
+        while self.peek_type() == TokenT::AS__ {
+            let token  = self.advance();
+            let target = self.parse_type()?;
+
+            expr = Expr {
+                kind: ExprKind::Cast { operand: Box::new(expr), target },
+                loc: token.loc,
+            };
         }
-        println!();
+
+        return Ok(expr);
     }
 
-    pub fn _dump_res_stack(&mut self, res: &Vec<f64>) {
-        for i in 0..res.len() {
-            print!("{} ", res[i]);
-            
+    pub fn parse_unary(&mut self) -> Result<Expr, io::Error> {
+        if self.peek_type() == TokenT::MINUS__ {
+            let token   = self.advance();
+            let operand = self.parse_unary()?;
+
+            return Ok(Expr {
+                kind: ExprKind::Unary { op: UnaryOp::Neg, operand: Box::new(operand) },
+                loc: token.loc,
+            });
         }
-        println!();
-    }
 
-    pub fn read_expression(&mut self, lex: &mut KasperLexer, mem_stack: &mut Stack) -> Result<f64, io::Error> {
-        match self.postfix(lex) {
-            Ok(()) => {
-                match self.evaluate(mem_stack) {
-                    Ok(v)  => Ok(v),
-                    Err(e) => return Err(e),
+        if self.peek_type() == TokenT::QM__ {
+            let token   = self.advance();
+            let operand = self.parse_unary()?;
 
-                }
-            },
-            Err(e) => return Err(e),
+            return Ok(Expr {
+                kind: ExprKind::Unary { op: UnaryOp::Not, operand: Box::new(operand) },
+                loc: token.loc,
+            });
         }
+
+        return self.parse_postfix();
     }
 
-    pub fn postfix(&mut self, lex: &mut KasperLexer) -> Result<(), io::Error> {
-        let mut tmp: Vec<Token> = Vec::new();        
-      
-        while lex.is_not_empty() {     
-            let token = match_lexer_token(lex.next()); // Next Token.
-            let prev = tmp.last_mut().cloned(); // An Option...
- 
-            if token.token_type == TokenT::NL__ {
-                break;
-            }
-            
-            if token.token_type == TokenT::COMMENT__ {
-                continue;
+    // expr[i], expr[a..b] and expr.name bind tighter than anything else.
+    pub fn parse_postfix(&mut self) -> Result<Expr, io::Error> {
+        let mut expr = self.parse_primary()?;
+
+        loop {
+            match self.peek_type() {
+                TokenT::OBRACKET__ => {
+                    let open = self.advance();
+                    expr = self.parse_index(expr, open.loc)?;
+                },
+                TokenT::DOT__ => {
+                    self.advance();
+                    let name = self.expect(TokenT::VARNAME__, "field name")?;
+                    expr = Expr { kind: ExprKind::Field { target: Box::new(expr), name: name.value }, loc: name.loc };
+                },
+                _ => return Ok(expr),
             }
+        }
+    }
 
-            if token.token_type == TokenT::NUMBER__ || token.token_type == TokenT::VARNAME__ {
-                self.iostack.push(token);
-                continue;
-            }
-            
-            if token.token_type == TokenT::OPAR__ {
-                tmp.push(token);
-                continue;
-            }
+    pub fn parse_index(&mut self, target: Expr, loc: Location) -> Result<Expr, io::Error> {
+        // The [ was eaten, [i] / [a..b] / [a..=b] / [a..] / [..b]
+        let target = Box::new(target);
+        let mut start: Option<Box<Expr>> = None;
 
-            if token.token_type == TokenT::CPAR__ {
-                if let Some(mut v) = prev {
-                    while v.token_type != TokenT::OPAR__ && tmp.len() > 0 {
-
-                        self.iostack.push(v.clone());
-                        tmp.pop();
-                        
-                        if let Some(other) = tmp.last_mut().cloned() {
-                            v = other; // Move..
-                            continue;
-                        }
-
-                        break;
-
-                    }
-                    
-                    if let Some(other) = tmp.last_mut().cloned() {
-                        if other.token_type == TokenT::OPAR__ {
-                            tmp.pop();
-                            continue;
-                        }
-                    }
-                }
+        if !matches!(self.peek_type(), TokenT::RANGE__ | TokenT::RANGE_INCL__) {
+            start = Some(Box::new(self.parse_expression()?));
+        }
 
-                let err = format!("{}:{}:{} Non-closed bracket Error.", lex.file_path, token.loc.row, token.loc.col);
-                return Err(make_error(&err));
-            }
-            
-
-            if self.is_in_prec(&token.value) {
-                if let Some(mut v) = prev {
-                    let c: &String = &v.value;
-                    
-                    if self.is_in_prec(c) {
-                        if self.is_greater(&token.value, c) {
-                            tmp.push(token);
-                            continue;
-                        }
-                        
-                        while self.is_less_eq(&token.value, &v.value) && v.value != token.value {
-                            
-                            self.iostack.push(v.clone());
-                            tmp.pop();
-                            if let Some(n) = tmp.last_mut().cloned() {
-                                v = n;
-                                if !self.is_in_prec(&v.value) {
-                                    break;
-                                }
-
-                                continue;
-                            }
-
-                            break;
-                        }
-
-                    }
+        let token = self.advance();
+
+        match (token.token_type, start) {
+            (TokenT::CBRACKET__, Some(index)) => {
+                return Ok(Expr { kind: ExprKind::Index { target, index }, loc });
+            },
+            (TokenT::RANGE__ | TokenT::RANGE_INCL__, start) => {
+                let inclusive = token.token_type == TokenT::RANGE_INCL__;
+                let mut end: Option<Box<Expr>> = None;
+
+                if self.peek_type() != TokenT::CBRACKET__ {
+                    end = Some(Box::new(self.parse_expression()?));
+                } else if inclusive {
+                    return Err(self.error_at(&token, "..= needs an end to include"));
                 }
-                
-                tmp.push(token);
-            }
-            
 
+                self.expect(TokenT::CBRACKET__, "]")?;
+                return Ok(Expr { kind: ExprKind::Slice { target, start, end, inclusive }, loc });
+            },
+            _ => return Err(self.unexpected(&token, "] or ..")),
         }
-        
-        while tmp.len() > 0 {
-            if let Some(v) = tmp.pop() {
-                self.iostack.push(v);
-                continue;
-            }
-            
-            break;
+    }
+
+    // Brackets close off the head of a for, a struct literal can go inside them.
+    pub fn with_structs<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, io::Error>) -> Result<T, io::Error> {
+        let no_structs  = self.no_structs;
+        self.no_structs = false;
+        let result = parse(self);
+        self.no_structs = no_structs;
+
+        return result;
+    }
+
+    pub fn parse_args(&mut self) -> Result<Vec<Expr>, io::Error> {
+        // The opening parent was eaten with the FUNC_CALL__ token.
+        let mut args: Vec<Expr> = Vec::new();
+        self.skip_new_lines();
+
+        if self.peek_type() == TokenT::CPAR__ {
+            self.advance();
+            return Ok(args);
         }
-        
-        for i in 0..self.iostack.len() {
-            if is_parent(self.iostack[i].token_type) {
-                let err = format!("{}:{}:{} Non-closed bracket Error.", lex.file_path, lex.row, lex.col);
-                return Err(make_error(&err));
+
+        loop {
+            self.skip_new_lines();
+            args.push(self.parse_expression()?);
+            self.skip_new_lines();
+
+            match self.peek_type() {
+                TokenT::COMA__ => {
+                    self.advance();
+                },
+                TokenT::CPAR__ => {
+                    self.advance();
+                    return Ok(args);
+                },
+                _ => return Err(self.unexpected(self.peek(), ", or )")),
             }
         }
-        
-        self.iostack.reverse();
-        Ok(())
-    }
-    
-    pub fn is_greater(&mut self, f: &String, s: &String) -> bool {
-        return self.prec_table[f] > self.prec_table[s];
-    }
-    pub fn is_less_eq(&mut self, f: &String, s: &String) -> bool {
-        return self.prec_table[f] <= self.prec_table[s];
     }
 
-    pub fn is_in_prec(&mut self, s: &String) -> bool {
-        return self.prec_table.contains_key(s);
+    pub fn parse_interp(&mut self) -> Result<Vec<Expr>, io::Error> {
+        // The INTERP_BEGIN__ was eaten, STRING__ pieces and { expr } holes follow until INTERP_END__.
+        let mut pieces: Vec<Expr> = Vec::new();
+
+        loop {
+            let token = self.advance();
+
+            match token.token_type {
+                TokenT::STRING__ => pieces.push(Expr { kind: ExprKind::Str(token.value), loc: token.loc }),
+                TokenT::OCURLY__ => {
+                    pieces.push(self.parse_expression()?);
+                    self.expect(TokenT::CCURLY__, "} to close the hole in the string")?;
+                },
+                TokenT::INTERP_END__ => return Ok(pieces),
+                _ => return Err(self.unexpected(&token, "} to close the hole in the string")),
+            }
+        }
     }
-/*
-    pub fn clear_stack(&mut self) {
-        self.iostack = Vec::new();
+
+    pub fn parse_array(&mut self) -> Result<ExprKind, io::Error> {
+        // The [ was eaten, [v; n] or a list of elements.
+        self.skip_new_lines();
+
+        if self.peek_type() == TokenT::CBRACKET__ {
+            self.advance();
+            return Ok(ExprKind::Array(Vec::new()));
+        }
+
+        let first = self.parse_expression()?;
+        self.skip_new_lines();
+
+        if self.peek_type() == TokenT::SEMICOLON__ {
+            self.advance();
+            self.skip_new_lines();
+            let count = self.parse_expression()?;
+            self.skip_new_lines();
+            self.expect(TokenT::CBRACKET__, "]")?;
+            return Ok(ExprKind::Repeat { value: Box::new(first), count: Box::new(count) });
+        }
+
+        match self.peek_type() {
+            TokenT::COMA__     => { self.advance(); },
+            TokenT::CBRACKET__ => {},
+            _ => return Err(self.unexpected(self.peek(), ", ; or ]")),
+        }
+
+        let mut elements = vec![first];
+        elements.append(&mut self.parse_elements()?);
+        return Ok(ExprKind::Array(elements));
     }
-*/    
-    pub fn evaluate(&mut self, mem_stack: &mut Stack) -> Result<f64, io::Error> {
-        let mut res: Vec<f64> = Vec::new();
- 
-
-        while self.iostack.len() > 0 {
-            
-            let l: Token = self.iostack.pop().unwrap();
-            // oprnd
-            if self.is_in_prec(&l.value) {
-                
-                let a: f64 = res.pop().unwrap();
-                let b: f64 = res.pop().unwrap();
-            
-                if l.token_type == TokenT::PLUS__ {
-                    res.push(a + b);  
-                    continue;    
-                } 
-                
-                if l.token_type == TokenT::MINUS__ {
-                    res.push(b - a);  
-                    continue;    
-                } 
-                
-                if l.token_type == TokenT::MULT__ {
-                    res.push(a * b);  
-                    continue;    
-                } 
-                
-                if l.token_type == TokenT::DIV__ {
-                    
-                    if a == 0.0 {
-                        let err = format!("{}:{} Division by zero Error.", 
-                                    l.loc.row, 
-                                    l.loc.col
-                            );
-
-                        return Err(make_error(&err));
-                    }
-                    
-                    res.push(b / a);  
-                    continue;    
-                } 
-
-                if l.token_type == TokenT::POW__ {
-                    res.push(b.powf(a));
-                    continue;
-                } 
-                
-                let err = format!("{}:{} Unsupported operand {}", 
-                              l.loc.row, 
-                              l.loc.col, 
-                              l.value
-                        );
-
-                return Err(make_error(&err));
-            }
-            
 
-            if l.token_type == TokenT::NUMBER__ || l.token_type == TokenT::FLOAT__ {
-                res.push(l.value.parse::<f64>().unwrap());
-                continue;
+    pub fn parse_elements(&mut self) -> Result<Vec<Expr>, io::Error> {
+        // The [ and the elements before were eaten, [a, b, c] and [] alike.
+        let mut elements: Vec<Expr> = Vec::new();
+
+        loop {
+            self.skip_new_lines();
+
+            if self.peek_type() == TokenT::CBRACKET__ {
+                self.advance();
+                return Ok(elements);
             }
-            
-            let undef = format!("{}:{} {} is undefined !",
-                            l.loc.row,
-                            l.loc.col,
-                            l.value
-                        );
-
-            if l.token_type == TokenT::VARNAME__ {
-                // find the value then push it..
-                match mem_stack.get_int(&l.value) {
-                    
-                    Some(val) => {
-                        res.push(val);
-                        continue;
-                    },
-
-                    None      => return Err(make_error(&undef))
-                }
+
+            elements.push(self.parse_expression()?);
+            self.skip_new_lines();
+
+            match self.peek_type() {
+                TokenT::COMA__     => { self.advance(); },
+                TokenT::CBRACKET__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or ]")),
             }
         }
+    }
+
+    pub fn parse_entries(&mut self) -> Result<Vec<(Expr, Expr)>, io::Error> {
+        // The { was eaten, {k: v, ...} and {} alike.
+        let mut entries: Vec<(Expr, Expr)> = Vec::new();
+
+        loop {
+            self.skip_new_lines();
 
-        if let Some(value) = res.last() {
-            return Ok(*value);
+            if self.peek_type() == TokenT::CCURLY__ {
+                self.advance();
+                return Ok(entries);
+            }
+
+            let key = self.parse_expression()?;
+            self.expect(TokenT::COLON__, ":")?;
+            self.skip_new_lines();
+            let value = self.parse_expression()?;
+            entries.push((key, value));
+            self.skip_new_lines();
+
+            match self.peek_type() {
+                TokenT::COMA__   => { self.advance(); },
+                TokenT::CCURLY__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or }")),
+            }
         }
-        
-        let err = format!("Value was lost in the stack.");
-        return Err(make_error(&err));
     }
-}
-
 
+    pub fn parse_fields(&mut self) -> Result<Vec<(String, Expr)>, io::Error> {
+        // The { was eaten, Name { a: expr, ... }.
+        let mut fields: Vec<(String, Expr)> = Vec::new();
 
+        loop {
+            self.skip_new_lines();
 
+            if self.peek_type() == TokenT::CCURLY__ {
+                self.advance();
+                return Ok(fields);
+            }
 
+            let name = self.expect(TokenT::VARNAME__, "field name")?.value;
+            self.expect(TokenT::COLON__, ":")?;
+            self.skip_new_lines();
+            fields.push((name, self.parse_expression()?));
+            self.skip_new_lines();
 
+            match self.peek_type() {
+                TokenT::COMA__   => { self.advance(); },
+                TokenT::CCURLY__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or }")),
+            }
+        }
+    }
 
+    pub fn parse_primary(&mut self) -> Result<Expr, io::Error> {
+        let token = self.advance();
 
+        let kind = match token.token_type {
+            TokenT::NUMBER__ => {
+                match token.value.parse::<u128>() {
+                    Ok(v)  => ExprKind::Int(v),
+                    Err(_) => return Err(self.error_at(&token, &format!("number literal {} is too large", token.value))),
+                }
+            },
+            TokenT::FLOAT__ => {
+                match token.value.parse::<f64>() {
+                    Ok(v)  => ExprKind::Float(v),
+                    Err(_) => return Err(self.error_at(&token, &format!("invalid float literal {}", token.value))),
+                }
+            },
+            TokenT::STRING__     => ExprKind::Str(token.value.clone()),
+            TokenT::INTERP_BEGIN__ => ExprKind::Interp(self.parse_interp()?),
+            TokenT::BOOL_TRUE__  => ExprKind::Bool(true),
+            TokenT::BOOL_FALSE__ => ExprKind::Bool(false),
+            TokenT::VARNAME__ if self.peek_type() == TokenT::OCURLY__ && !self.no_structs => {
+                self.advance();
+                ExprKind::StructLit { name: token.value.clone(), fields: self.parse_fields()? }
+            },
+            TokenT::VARNAME__    => ExprKind::Var(token.value.clone()),
+            TokenT::FUNC_CALL__  => {
+                let args = self.with_structs(|p| p.parse_args())?;
+                ExprKind::Call { name: token.value.clone(), args }
+            },
+            TokenT::OBRACKET__ => self.with_structs(|p| p.parse_array())?,
+            TokenT::OCURLY__   => ExprKind::Map(self.with_structs(|p| p.parse_entries())?),
+            TokenT::MATCH__    => ExprKind::Match(Box::new(self.parse_match(&token, false)?)),
+            TokenT::OPAR__ => {
+                let expr = self.with_structs(|p| p.parse_expression())?;
+
+                if self.peek_type() != TokenT::CPAR__ {
+                    return Err(self.error_at(&token, "Non-closed bracket Error."));
+                }
 
+                self.advance();
+                return Ok(expr);
+            },
+            _ => return Err(self.unexpected(&token, "an expression")),
+        };
 
+        return Ok(Expr { kind, loc: token.loc });
+    }
+}
@@ -1,441 +1,572 @@
-// TODO: Parse complex arithmatics.
-// TODO: 
+// Recursive descent parser, turns the token stream into a Program.
 use std::io;
+use std::collections::HashMap;
+use crate::ast::*;
 use crate::enums::*;
-use crate::stack::*;
-use crate::lexer::{ KasperLexer, match_lexer_token };
-use crate::util::{ make_error }; // not_implemented
-use crate::token::Token;
-use crate::expr_parser::ArithmaticParser;
-
-#[allow(dead_code)]
-pub struct Variable {
-    name:             String,
-    value:            String,
-    var_parsed_type:  TokenT,
-    declared_type:    TokenT,
+use crate::util::make_error_at;
+use crate::token::{ Token, Location };
+use crate::types::Type;
+
+pub struct KasperParser<'a> {
+    pub file_path:  &'a str,
+    tokens:         Vec<Token>,
+    cur:            usize,
+    eof:            Token,
+    depth:          usize, // How many blocks deep we are.
+    in_process:     bool,  // Inside the body of a process, return is allowed.
+    pub loops:      usize, // Enclosing loops, break and continue need one.
+    pub no_structs: bool,  // In a for head, Name { is the body of the loop, not a struct literal.
+    pub prec_table: HashMap<TokenT, i32>,
 }
 
-#[allow(dead_code)]
-impl Variable { 
-    pub fn new(nm: String, v: String, dt: TokenT, pt: TokenT) -> Self {
-        Self {
-            name: nm,
-            value: v,
-            var_parsed_type: pt,
-            declared_type: dt,
+impl<'a> KasperParser<'a> {
+
+    pub fn new(path: &'a str, tokens: Vec<Token>) -> Self {
+        let mut eof = Token::empty();
+
+        if let Some(last) = tokens.last() {
+            eof.loc = last.loc;
         }
-    }
-    
-    pub fn empty() -> Self {
-        
+
         Self {
-            name: "".to_string(),
-            value: "".to_string(),
-            var_parsed_type: TokenT::NONE__,
-            declared_type: TokenT::NONE__,
+            file_path: path,
+            tokens,
+            cur: 0,
+            eof,
+            depth: 0,
+            in_process: false,
+            loops: 0,
+            no_structs: false,
+            prec_table: make_prec_table(),
         }
+    }
 
+    pub fn peek(&self) -> &Token {
+        return self.peek_at(0);
     }
 
-    pub fn is_number(&mut self) -> bool {
-        return self.declared_type == TokenT::FLOAT__  || self.declared_type == TokenT::INT_T  || self.declared_type == TokenT::INT_T_64;
+    pub fn peek_at(&self, offset: usize) -> &Token {
+        if let Some(token) = self.tokens.get(self.cur + offset) {
+            return token;
+        }
+
+        return &self.eof;
     }
-}
 
-// STACK MAPS:
+    // Location of the last consumed token.
+    pub fn prev_loc(&self) -> Location {
+        if self.cur > 0 {
+            return self.tokens[self.cur - 1].loc;
+        }
 
-/*
- 
-str_map:           HashMap<String, String>,
-int_map_8:         HashMap<String, i8>, // 8 bit..
-int_map_16:        HashMap<String, i16>, // 16 bit..
-int_map:           HashMap<String, i32>, // 32 bit..
-int_map_64:        HashMap<String, i64>, // 64 bit..
-int_map_128:       HashMap<String, i128>, // 128 bit..
-int_map_8_array:   HashMap<String, Vec<i8>>, // 8 bit..
-int_map_16_array:  HashMap<String, Vec<i16>>, // 16 bit..
-int_map_array:     HashMap<String, Vec<i32>>, // 32 bit..
-int_map_64_array:  HashMap<String, Vec<i64>>, // 64 bit..
-int_map_128_array: HashMap<String, Vec<i128>>, // 128 bit..  
+        return self.eof.loc;
+    }
 
+    pub fn peek_type(&self) -> TokenT {
+        return self.peek().token_type;
+    }
 
-*/
+    pub fn is_not_empty(&self) -> bool {
+        return self.cur < self.tokens.len();
+    }
 
-pub struct KasperParser<'a> {
-    pub lexer: KasperLexer<'a>, // Reads the code.. 
-    pub ap: ArithmaticParser,   // Read math expressions...
-    stack: Stack,               // Stores variables...
-}
+    pub fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
 
-#[allow(unreachable_patterns, dead_code)]  
-impl<'a> KasperParser<'a> {
-    
-     pub fn new(lex: KasperLexer<'a>) -> Self {
-        Self {
-            lexer: lex,
-            stack: Stack::new(),
-            ap: ArithmaticParser::new(),
+        if self.is_not_empty() {
+            self.cur += 1;
         }
+
+        return token;
     }
-    
-        
-    pub fn parse_lexer(&mut self) -> Result<(), io::Error> {
-        let mut token = match_lexer_token(self.lexer.next());        
 
-        if token.token_type == TokenT::NONE__ {
-            
-            let err = format!("{}:{}:{} Syntax error, unrecognized token { }",
-                               self.lexer.file_path, 
-                               token.loc.col, 
-                               token.loc.row, 
-                               token.value  
-                            );
+    pub fn error_at(&self, token: &Token, text: &str) -> io::Error {
+        return make_error_at(self.file_path, &token.loc, text);
+    }
 
-            return Err(make_error(&err));
+    pub fn unexpected(&self, token: &Token, expected: &str) -> io::Error {
+        if token.token_type == TokenT::NONE__ {
+            return self.error_at(token, &format!("expected {} but reached the end of the file", expected));
         }
-        
+
         if token.token_type == TokenT::NL__ {
-           return Ok(());
+            return self.error_at(token, &format!("expected {} but found a new line", expected));
         }
 
-        if token.token_type != TokenT::COMMENT__ { 
-            
-            if token.token_type == TokenT::FUNC_CALL__ {
-                
-                if token.value == String::from(WRITE) {
-                    return self.parse_print();
-                }
+        return self.error_at(token, &format!("expected {} but found {}", expected, token.value));
+    }
 
-                let err = format!("{}:{}:{} {} is not defined", 
-                                  self.lexer.file_path, 
-                                  token.loc.col, 
-                                  token.loc.row, 
-                                  token.value
-                            );
+    pub fn expect(&mut self, t: TokenT, expected: &str) -> Result<Token, io::Error> {
+        if self.peek_type() == t {
+            return Ok(self.advance());
+        }
+
+        return Err(self.unexpected(self.peek(), expected));
+    }
+
+    pub fn skip_separators(&mut self) {
+        while self.peek_type() == TokenT::NL__ || self.peek_type() == TokenT::SEMICOLON__ {
+            self.advance();
+        }
+    }
 
-                return Err(make_error(&err));
+    // Inside brackets a new line does not end anything.
+    pub fn skip_new_lines(&mut self) {
+        while self.peek_type() == TokenT::NL__ {
+            self.advance();
+        }
+    }
+
+    pub fn parse_program(&mut self) -> Result<Program, io::Error> {
+        let mut stmts: Vec<Stmt> = Vec::new();
+
+        loop {
+            self.skip_separators();
+
+            if !self.is_not_empty() {
+                break;
             }
 
-            if token.token_type == TokenT::KEY_WORD_DEFINE__ {
-                return self.parse_def();
+            stmts.push(self.parse_statement()?);
+        }
+
+        return Ok(Program { stmts });
+    }
+
+    // A statement ends at a new line, a semicolon, the end of the file or the } of its block.
+    pub fn end_statement(&mut self) -> Result<(), io::Error> {
+        match self.peek_type() {
+            TokenT::NL__ | TokenT::SEMICOLON__ => {
+                self.advance();
+                return Ok(());
+            },
+            TokenT::NONE__ | TokenT::CCURLY__ => return Ok(()),
+            _ => return Err(self.unexpected(self.peek(), "the end of the statement")),
+        }
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Stmt, io::Error> {
+        let token = self.peek().clone();
+
+        let stmt = match token.token_type {
+            TokenT::KEY_WORD_DEFINE__ | TokenT::CONST__ => self.parse_def()?,
+            TokenT::IF__              => self.parse_branching()?,
+            TokenT::KEY_WORD_PROCESS__ => self.parse_process()?,
+            TokenT::STRUCT__          => self.parse_struct()?,
+            TokenT::ENUM__            => self.parse_enum()?,
+            TokenT::MATCH__           => {
+                self.advance();
+                let m = self.parse_match(&token, true)?;
+                Stmt { kind: StmtKind::Match(m), loc: token.loc }
+            },
+            TokenT::RETURN__          => self.parse_return()?,
+            TokenT::WHILE__           => self.parse_while()?,
+            TokenT::FOR__             => self.parse_for()?,
+            TokenT::BREAK__ | TokenT::CONTINUE__ => self.parse_loop_jump()?,
+            TokenT::OCURLY__          => {
+                let block = self.parse_block()?;
+                Stmt { kind: StmtKind::Block(block), loc: token.loc }
+            },
+            TokenT::VARNAME__ if self.peek_at(1).token_type == TokenT::EQUAL__ => self.parse_assign()?,
+            _ => {
+                let expr = self.parse_expression()?;
+
+                if self.peek_type() == TokenT::EQUAL__ {
+                    self.parse_assign_to(expr)?
+                } else {
+                    Stmt { kind: StmtKind::Expr(expr), loc: token.loc }
+                }
+            },
+        };
+
+        self.end_statement()?;
+        return Ok(stmt);
+    }
+
+    pub fn parse_block(&mut self) -> Result<Block, io::Error> {
+        // { stmt* }
+        let open = self.expect(TokenT::OCURLY__, "{")?;
+        let mut stmts: Vec<Stmt> = Vec::new();
+
+        self.depth += 1;
+
+        loop {
+            self.skip_separators();
+
+            if self.peek_type() == TokenT::CCURLY__ {
+                let close = self.advance();
+                self.depth -= 1;
+                return Ok(Block { stmts, end: close.loc });
             }
 
-            if token.token_type == TokenT::VARNAME__ { // var
-                return self.assign_variable(&mut token); 
+            if !self.is_not_empty() {
+                return Err(self.error_at(&open, "unclosed scope, add } to close it"));
             }
-          
-            return Ok(());
+
+            stmts.push(self.parse_statement()?);
         }
+    }
+
+    pub fn parse_param(&mut self) -> Result<Param, io::Error> {
+        // name -> T
+        let token = self.expect(TokenT::VARNAME__, "parameter name")?;
+        self.expect(TokenT::THIN_ARROW__, "->")?;
+        let declared_type = self.parse_type()?;
 
-        return Ok(());
-    
+        return Ok(Param { name: token.value, declared_type, loc: token.loc });
     }
-    
-    // pub fn parse_condition(&mut self) -> Result<bool, io::Error> {
-    //     let mut parsed_condition: bool = true;
-    //     let token: Token           = match_lexer_token(self.lexer.next());
-        
-    //     if token.token_type == TokenT::VARNAME__ {
-    //         // Parse the codition that is inside.
-    //         if self.stack.defined(&token.value) {
-    //             // | x |
-
-    //             if self.lexer.get_next() == PIPE { 
-    //                 self.lexer.chop(); // Chop the pipe.
-    //                 if let Some(v) = self.stack.get_from_bool_map(&token.value) {
-    //                     return Ok(*v);
-    //                 }
-
-    //             }
-                
-    //             let err = format!("{}:{}:{} expected a PIPE (|) but found {}", 
-    //                       self.lexer.file_path, 
-    //                       token.loc.row, 
-    //                       token.loc.col,
-    //                       token.value
-    //                 );
-
-    //             return Err(make_error(&err));
-    //         }
-
-    //         let err = format!("{}:{}:{} {} is Undefined", 
-    //                   self.lexer.file_path, 
-    //                   token.loc.row, 
-    //                   token.loc.col, 
-    //                   token.value
-    //             );
-
-    //         return Err(make_error(&err));
-    //     }
-        
-    //     if token.token_type == TokenT::NUMBER__  {
-            
-    //         not_implemented("Condition are not Implemented yet!");
-    //         let lhs: i128 = token.value.parse::<i128>().unwrap(); // LHS
-    //         let tmp = match_lexer_token(self.lexer.next());
-            
-    //     }
-        
-    //     if token.token_type == TokenT::STRING__  {
-    //         not_implemented("Condition are not Implemented yet!");
-    //     }
-        
-    //     return Ok(true);
-    // }
-
-    // pub fn parse_branching(&mut self) -> Result<bool, io::Error>{
-    //     // if | x == 0 | { ... } else { ... }
-    //     let token = match_lexer_token(self.lexer.next());    
-        
-    //     if token.token_type == TokenT::PIPE__ {
-    //         match self.parse_condition() {
-    //             Ok(v) => return Ok(v),
-    //             Err(e) => return Err(e),
-    //         }
-    //     }
-        
-    //     let err = format!("{}:{}:{} expected a pipe | but got {} instead", self.lexer.file_path, token.loc.row, token.loc.col, token.value);
-    //     return Err(make_error(&err));
-    // }
-    
-    pub fn assign_variable(&mut self, token: &mut Token) -> Result<(), io::Error> {
-        
-        let variable_name: &String = &token.value.clone();
-        
-        if self.stack.defined(&token.value) {    
-            if match_lexer_token(self.lexer.next()).token_type == TokenT::EQUAL__ {
-                // Assign variable. = val.
-                self.lexer.chop();
-                *token = match_lexer_token(self.lexer.next()); // Value..
-                if token.token_type == TokenT::NUMBER__ {
-            
-                    if self.stack.int_map.contains_key(variable_name) { 
-                        let v = token.value.parse::<i32>().unwrap();            
-                        self.stack.push_int_map(variable_name, v);
-                        return Ok(());
-                    }     
-                
-                    if self.stack.int_map_64.contains_key(variable_name) {
-                        let v = token.value.parse::<i64>().unwrap();            
-                        self.stack.push_int_map_64(variable_name, v);
-                        return Ok(());
-                    }
-                     
-                }        
-        
-                if token.token_type == TokenT::STRING__ {
-                    if self.stack.str_map.contains_key(variable_name) {
-                        self.stack.push_str_map(variable_name, token.value.clone());
-                        return Ok(());
-                    } 
-                }
-        
-                if token.token_type == TokenT::BOOL_FALSE__ || token.token_type == TokenT::BOOL_TRUE__ {
-                    let v: bool = token.token_type == TokenT::BOOL_TRUE__;
-            
-                    if self.stack.bool_map.contains_key(variable_name) {
-                        self.stack.push_bool_map(variable_name, v); // True or False
-                        return Ok(());
-                    } 
 
+    pub fn parse_process(&mut self) -> Result<Stmt, io::Error> {
+        // process name(a -> T, ...) [-> T] { ... }
+        let process = self.advance();
+
+        if self.depth > 0 || self.in_process {
+            return Err(self.error_at(&process, "a process can only be declared at the top level"));
+        }
+
+        let name = self.advance();
+
+        match name.token_type {
+            TokenT::FUNC_CALL__ => {}, // The lexer ate the (.
+            TokenT::VARNAME__   => { self.expect(TokenT::OPAR__, "(")?; },
+            _ => return Err(self.unexpected(&name, "process name")),
+        }
+
+        if BUILTINS.contains(&name.value.as_str()) {
+            return Err(self.error_at(&name, &format!("{} is a built-in and can not be redefined", name.value)));
+        }
+
+        let mut params: Vec<Param> = Vec::new();
+
+        if self.peek_type() != TokenT::CPAR__ {
+            loop {
+                params.push(self.parse_param()?);
+
+                if self.peek_type() != TokenT::COMA__ {
+                    break;
                 }
 
-                let err = format!("{}:{}:{} unexpected token {}", self.lexer.file_path, token.loc.row, token.loc.col, token.value);
-                return Err(make_error(&err));
+                self.advance();
             }
-                
-            let err = format!("{}:{}:{} expected = got {} instead.", self.lexer.file_path, token.loc.col, token.loc.row, token.value);
-            return Err(make_error(&err));
+        }
 
+        self.expect(TokenT::CPAR__, ")")?;
 
+        let mut return_type: Option<Type> = None;
+
+        if self.peek_type() == TokenT::THIN_ARROW__ {
+            self.advance();
+            return_type = Some(self.parse_type()?);
         }
 
-        let err = format!("{}:{}:{} {} is not defined try define {} -> T", self.lexer.file_path, token.loc.col, token.loc.row, token.value,token.value);
-        return Err(make_error(&err));         
+        // A loop around the declaration does not reach into the body.
+        let loops = self.loops;
+        self.in_process = true;
+        self.loops = 0;
+        let body = self.parse_block();
+        self.in_process = false;
+        self.loops = loops;
+
+        return Ok(Stmt {
+            kind: StmtKind::Process(Proc { name: name.value, params, return_type, body: body?, loc: name.loc }),
+            loc: process.loc,
+        });
     }
 
-    pub fn parse_lhs(&mut self) -> Result<Variable, io::Error> {
-        // Parse var -> T
-        let mut var   = Variable::empty();
-        let mut token = match_lexer_token(self.lexer.next());  // Get VarName.
-        
-        if token.token_type == TokenT::VARNAME__ {
-            var.name = token.value;
-            token = match_lexer_token(self.lexer.next()); // ->
-            if token.token_type == TokenT::THIN_ARROW__ {
-                token = match_lexer_token(self.lexer.next()); // T 
-                
-                if token.token_type == TokenT::STRING_T {
-                    var.declared_type = TokenT::STRING_T;
-                    
-                    self.stack.push_str_map(&var.name, "".to_string()); // Add var with name
-                                                                              // and default value
-                    return Ok(var);
-                }
+    pub fn parse_struct(&mut self) -> Result<Stmt, io::Error> {
+        // struct Name { a -> T, ... }, the fields are separated by commas or new lines.
+        let token = self.advance();
 
-                if token.token_type == TokenT::INT_T {
-                    var.declared_type = TokenT::INT_T;
-                    self.stack.push_int_map(&var.name, 0);
-                    return Ok(var);
-                }
+        if self.depth > 0 || self.in_process {
+            return Err(self.error_at(&token, "a struct can only be declared at the top level"));
+        }
 
-                if token.token_type == TokenT::INT_T_64 {
-                    var.declared_type = TokenT::INT_T_64;
-                    self.stack.push_int_map_64(&var.name, 0);
-                    return Ok(var);
+        let name = self.expect(TokenT::VARNAME__, "struct name")?;
+        self.expect(TokenT::OCURLY__, "{")?;
 
-                }
-                
-                if token.token_type == TokenT::BOOL_T {
-                    var.declared_type = TokenT::BOOL_T;
-                    self.stack.push_bool_map(&var.name, true);
-                    return Ok(var);
+        let mut fields: Vec<Param> = Vec::new();
 
-                }
+        loop {
+            self.skip_new_lines();
 
-                let err = format!("{}:{} unsupported type {}", token.loc.row, token.loc.col, token.value);
-                return Err(make_error(&err));
+            if self.peek_type() == TokenT::CCURLY__ {
+                self.advance();
+                break;
             }
 
-            let err = format!("{}:{} expected -> but found {}", token.loc.row, token.loc.col, token.value);
-            return Err(make_error(&err));
- 
+            let field = self.parse_param()?;
+
+            if let Some(other) = fields.iter().find(|f| f.name == field.name) {
+                let err = format!("field {} is already declared at {}:{}:{}", field.name, self.file_path, other.loc.row, other.loc.col);
+                return Err(make_error_at(self.file_path, &field.loc, &err));
+            }
+
+            fields.push(field);
+
+            match self.peek_type() {
+                TokenT::COMA__ | TokenT::NL__ => { self.advance(); },
+                TokenT::CCURLY__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or }")),
+            }
         }
 
-        let err = format!("{}:{} expected variable name, found {} instead.", token.loc.row, token.loc.col, token.token_type);
-        return Err(make_error(&err));
-     
+        return Ok(Stmt {
+            kind: StmtKind::Struct(StructDecl { name: name.value, fields, loc: name.loc }),
+            loc: token.loc,
+        });
     }
-    
 
-    pub fn register_var(&mut self, mut variable: Variable) -> Result<(), io::Error> {
+    pub fn parse_enum(&mut self) -> Result<Stmt, io::Error> {
+        // enum Name { A, B(T, ...), ... }, the variants are separated by commas or new lines.
+        let token = self.advance();
 
-        if variable.declared_type == TokenT::STRING_T  {
-            let token = match_lexer_token(self.lexer.next());
-            
-            if token.token_type == TokenT::STRING__ {
-                self.stack.push_str_map(&variable.name, token.value);
-                return Ok(());
+        if self.depth > 0 || self.in_process {
+            return Err(self.error_at(&token, "an enum can only be declared at the top level"));
+        }
+
+        let name = self.expect(TokenT::VARNAME__, "enum name")?;
+        self.expect(TokenT::OCURLY__, "{")?;
+
+        let mut variants: Vec<Variant> = Vec::new();
+
+        loop {
+            self.skip_new_lines();
+
+            if self.peek_type() == TokenT::CCURLY__ {
+                self.advance();
+                break;
             }
 
-            let err = format!("{}:{}:{} unexpected token, expected string but found {}",
-                        self.lexer.file_path,
-                        token.loc.row,
-                        token.loc.col,
-                        token.token_type
-                    );
-
-            return Err(make_error(&err));
-        } 
-        
-        if variable.declared_type == TokenT::BOOL_T {
-            let token = match_lexer_token(self.lexer.next());
-
-            if token.token_type == TokenT::BOOL_TRUE__ || token.token_type == TokenT::BOOL_FALSE__ {
-                let v = token.token_type == TokenT::BOOL_TRUE__;
-                self.stack.push_bool_map(&variable.name, v);
-                return Ok(());
+            let variant = self.parse_variant()?;
+
+            if let Some(other) = variants.iter().find(|v| v.name == variant.name) {
+                let err = format!("variant {} is already declared at {}:{}:{}", variant.name, self.file_path, other.loc.row, other.loc.col);
+                return Err(make_error_at(self.file_path, &variant.loc, &err));
+            }
+
+            variants.push(variant);
+
+            match self.peek_type() {
+                TokenT::COMA__ | TokenT::NL__ => { self.advance(); },
+                TokenT::CCURLY__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or }")),
             }
+        }
+
+        if variants.is_empty() {
+            return Err(self.error_at(&name, &format!("enum {} needs at least one variant", name.value)));
+        }
+
+        return Ok(Stmt {
+            kind: StmtKind::Enum(EnumDecl { name: name.value, variants, loc: name.loc }),
+            loc: token.loc,
+        });
+    }
+
+    pub fn parse_variant(&mut self) -> Result<Variant, io::Error> {
+        // A / B(T, ...)
+        let name = self.advance();
+
+        match name.token_type {
+            TokenT::FUNC_CALL__ => {}, // The lexer ate the (.
+            TokenT::VARNAME__ if self.peek_type() == TokenT::OPAR__ => { self.advance(); },
+            TokenT::VARNAME__ => return Ok(Variant { name: name.value, fields: Vec::new(), loc: name.loc }),
+            _ => return Err(self.unexpected(&name, "variant name")),
+        }
+
+        if self.peek_type() == TokenT::CPAR__ {
+            return Err(self.error_at(&name, &format!("a variant without a payload is written {}, without ()", name.value)));
+        }
+
+        let mut fields: Vec<Type> = Vec::new();
 
-            let err = format!("{}:{}:{} unexpected token, expected boolean but found {}",
-                        self.lexer.file_path,
-                        token.loc.row,
-                        token.loc.col,
-                        token.token_type
-                );
-
-            return Err(make_error(&err));
-        }
-
-
-        if variable.is_number() {
-            match self.ap.read_expression(&mut self.lexer, &mut self.stack)
-            {
-                Ok(v) => {
-
-                    if variable.declared_type == TokenT::INT_T { // Reg as i32
-                        self.stack.push_int_map(&variable.name, v as i32);
-                        return Ok(());
-                    }
-                    
-                    if variable.declared_type == TokenT::INT_T_64 { // Reg as i64
-                        self.stack.push_int_map_64(&variable.name, v as i64);
-                        return Ok(());
-                    }
-                    
-                    let err = format!("unsupported type {}", variable.declared_type);
-                    return Err(make_error(&err));
-                },
-
-                Err(e) => return Err(e),
-            };
+        loop {
+            fields.push(self.parse_type()?);
+
+            if self.peek_type() != TokenT::COMA__ {
+                break;
+            }
+
+            self.advance();
+        }
+
+        self.expect(TokenT::CPAR__, ")")?;
+        return Ok(Variant { name: name.value, fields, loc: name.loc });
+    }
+
+    pub fn parse_range(&mut self, start: Expr) -> Result<Range, io::Error> {
+        // a..b / a..=b [step s], a was parsed by the caller.
+        let token = self.advance();
+
+        let inclusive = match token.token_type {
+            TokenT::RANGE__      => false,
+            TokenT::RANGE_INCL__ => true,
+            _ => return Err(self.unexpected(&token, ".. or ..=")),
         };
-        
-                
-        let err = format!("{}:{}:{} Syntax err", 
-                    self.lexer.file_path,
-                    self.lexer.row,
-                    self.lexer.col
-                );
-
-        return Err(make_error(&err));
+
+        let end = self.parse_expression()?;
+        let mut step: Option<Expr> = None;
+
+        if self.peek_type() == TokenT::STEP__ {
+            self.advance();
+            step = Some(self.parse_expression()?);
+        }
+
+        return Ok(Range { start, end, inclusive, step });
     }
 
-    pub fn parse_def(&mut self) -> Result<(), io::Error> {
-        
-        match self.parse_lhs() {
-            Ok(variable) => {
-                // successs parsing the type..
-                if match_lexer_token(self.lexer.next()).token_type == TokenT::EQUAL__ {
-                    self.lexer.chop();
-                    return self.register_var(variable);
-                }
+    pub fn parse_for(&mut self) -> Result<Stmt, io::Error> {
+        // for i in a..b [step s] { ... } / for x in xs { ... }
+        let token = self.advance();
+        let var   = self.expect(TokenT::VARNAME__, "loop variable name")?.value;
 
-                return Ok(());
-            },
+        self.expect(TokenT::IN__, "in")?;
+
+        // The { after the head opens the body, xs { is not a struct literal.
+        let no_structs  = self.no_structs;
+        self.no_structs = true;
+        let start = self.parse_expression()?;
 
-            Err(e) => return Err(e),
-            _ => panic!("unreachable!"),
-        } 
+        if matches!(self.peek_type(), TokenT::RANGE__ | TokenT::RANGE_INCL__) {
+            let range = self.parse_range(start)?;
+            self.no_structs = no_structs;
+            let body  = self.parse_loop_body()?;
+            return Ok(Stmt { kind: StmtKind::For { var, range, body }, loc: token.loc });
+        }
 
+        self.no_structs = no_structs;
+        let body = self.parse_loop_body()?;
+        return Ok(Stmt { kind: StmtKind::ForEach { var, iterable: start, body }, loc: token.loc });
     }
-       
-
-    pub fn parse_print(&mut self) -> Result<(), io::Error> {
-        let token = match_lexer_token(self.lexer.next());       
-        if token.token_type == TokenT::STRING__ {
-            
-            // Get the print value.
-            let val = token.value.clone();    
-            
-            if match_lexer_token(self.lexer.next()).token_type == TokenT::CPAR__ {
-                print!("{}", val);
-                return Ok(());
+
+    // The block of a loop, break and continue are allowed in it.
+    pub fn parse_loop_body(&mut self) -> Result<Block, io::Error> {
+        self.loops += 1;
+        let body = self.parse_block();
+        self.loops -= 1;
+
+        return body;
+    }
+
+    pub fn parse_assign_to(&mut self, target: Expr) -> Result<Stmt, io::Error> {
+        // xs[i] = expr / p.x = expr, the target was parsed as an expression.
+        let equal = self.expect(TokenT::EQUAL__, "=")?;
+
+        if !matches!(target.kind, ExprKind::Index { .. } | ExprKind::Field { .. }) {
+            return Err(self.error_at(&equal, "can not assign to this expression, only to a variable, an element or a field"));
+        }
+
+        let value = self.parse_expression()?;
+        let loc   = target.loc;
+
+        return Ok(Stmt { kind: StmtKind::AssignTo { target, value }, loc });
+    }
+
+    pub fn parse_loop_jump(&mut self) -> Result<Stmt, io::Error> {
+        // break / continue
+        let token = self.advance();
+
+        if self.loops == 0 {
+            return Err(self.error_at(&token, &format!("{} outside of a loop", token.value)));
+        }
+
+        let kind = if token.token_type == TokenT::BREAK__ { StmtKind::Break } else { StmtKind::Continue };
+        return Ok(Stmt { kind, loc: token.loc });
+    }
+
+    pub fn parse_return(&mut self) -> Result<Stmt, io::Error> {
+        // return [expr]
+        let token = self.advance();
+
+        if !self.in_process {
+            return Err(self.error_at(&token, "return outside of a process"));
+        }
+
+        let mut value: Option<Expr> = None;
+
+        if !matches!(self.peek_type(), TokenT::NL__ | TokenT::SEMICOLON__ | TokenT::CCURLY__ | TokenT::NONE__) {
+            value = Some(self.parse_expression()?);
+        }
+
+        return Ok(Stmt { kind: StmtKind::Return(value), loc: token.loc });
+    }
+
+    pub fn parse_type(&mut self) -> Result<Type, io::Error> {
+        let token = self.advance(); // T or [T]
+
+        if token.token_type == TokenT::OBRACKET__ {
+            let t = self.parse_type()?;
+            self.expect(TokenT::CBRACKET__, "]")?;
+            return Ok(Type::Array(Box::new(t)));
+        }
+
+        if token.token_type == TokenT::MAP_T {
+            // map[K, V]
+            self.expect(TokenT::OBRACKET__, "[")?;
+            let key_token = self.peek().clone();
+            let k = self.parse_type()?;
+
+            if !k.is_key() {
+                return Err(self.error_at(&key_token, &format!("a map key can be an integer, a string or a bool, not {}", k)));
             }
-            
-            // Error
-            let mut err_text = format!("{}:{}:{} unclosed parent found {} expected )..\n", self.lexer.file_path, token.loc.row, token.loc.col, token.value);
-            err_text    += &format!("maybe you meant: print?.");
-            return Err(make_error(&err_text));
-            
-        }
-        
+
+            self.expect(TokenT::COMA__, ",")?;
+            let v = self.parse_type()?;
+            self.expect(TokenT::CBRACKET__, "]")?;
+            return Ok(Type::Map(Box::new(k), Box::new(v)));
+        }
+
+        // Any other name is a struct or an enum, it is looked up when the program runs.
         if token.token_type == TokenT::VARNAME__ {
-            
-            let k = &token.value; 
-                        
-            if self.stack.print_variable(k) {
-                return Ok(());
-            }
+            return Ok(Type::Named(token.value));
+        }
 
-            let err_text = format!("{}:{} {} is not defined.\n", 
-                                   token.loc.row, 
-                                   token.loc.col, 
-                                   k);
-            return Err(make_error(&err_text));
+        match Type::from_token(token.token_type) {
+            Some(t) => return Ok(t),
+            None    => return Err(self.error_at(&token, &format!("unsupported type {}", token.value))),
         }
+    }
+
+    pub fn parse_def(&mut self) -> Result<Stmt, io::Error> {
+        // define var -> T [= expr] / define var = expr / const var [-> T] = expr
+        let define   = self.advance();
+        let constant = define.token_type == TokenT::CONST__;
+        let name     = self.expect(TokenT::VARNAME__, "variable name")?.value;
+
+        let mut declared_type: Option<Type> = None;
+        let mut init: Option<Expr> = None;
+
+        if self.peek_type() == TokenT::THIN_ARROW__ {
+            self.advance();
+            declared_type = Some(self.parse_type()?);
+        }
+
+        if constant && self.peek_type() != TokenT::EQUAL__ {
+            let err = format!("const {} needs its value where it is defined, write const {} = value", name, name);
+            return Err(self.error_at(&define, &err));
+        }
+
+        // Without a type the value gives it, so the value is needed.
+        if declared_type.is_none() || self.peek_type() == TokenT::EQUAL__ {
+            self.expect(TokenT::EQUAL__, "-> T or =")?;
+            init = Some(self.parse_expression()?);
+        }
+
+        return Ok(Stmt {
+            kind: StmtKind::Define { name, declared_type, init, constant },
+            loc: define.loc,
+        });
+    }
+
+    pub fn parse_assign(&mut self) -> Result<Stmt, io::Error> {
+        // var = expr
+        let token = self.advance();
+        self.expect(TokenT::EQUAL__, "=")?;
+        let value = self.parse_expression()?;
 
-        return Ok(());
+        return Ok(Stmt {
+            kind: StmtKind::Assign { name: token.value, value },
+            loc: token.loc,
+        });
     }
 }
@@ -3,8 +3,7 @@
 use std::fs::File;
 use std::io;
 use std::io::Read;
-use std::collections::HashMap;
-use std::process::exit;
+use std::collections::{ HashMap, VecDeque };
 
 // CUSTOM.
 use crate::enums::*;
@@ -13,12 +12,14 @@ use crate::util::make_error;
 
 pub struct KasperLexer<'a> {
     pub file_path: &'a str,
-    pub source:    Vec<u8>,
+    pub source:    Vec<char>, // The file decoded as UTF-8, a char per index.
     pub cur:       usize,
     pub row:       usize,
     pub col:       usize,
     pub size:      usize,
     token_table: HashMap<char, TokenT>,
+    double_token_table: HashMap<&'static str, TokenT>,
+    pending: VecDeque<Token>, // Tokens lexed ahead, the pieces of an interpolated string.
 } 
 
 
@@ -35,15 +36,17 @@ impl<'a> KasperLexer<'a> {
             col:       1,
             size:      0,
             token_table: make_token_table(),
+            double_token_table: make_double_token_table(),
+            pending: VecDeque::new(),
         };
     }
 
 
     pub fn get_char(&mut self, index: usize) -> char { 
         
-        if self.is_not_empty()
+        if index < self.size
         {
-            return char::from(self.source[index]);
+            return self.source[index];
         }
 
         return '\0';
@@ -79,13 +82,13 @@ impl<'a> KasperLexer<'a> {
 
     pub fn chop(&mut self) -> usize {
         if self.is_not_empty()  {
-            self.cur += 1;
-    
             let c: char = self.get_current();
+            self.cur += 1;
                         
+            // Leaving a new line puts us at the start of the next row.
             if c == NL {
                 self.row  += 1;
-                self.col =  0;
+                self.col =  1;
                  
                 return self.cur;
             }
@@ -98,52 +101,22 @@ impl<'a> KasperLexer<'a> {
         
     pub fn read(&mut self) -> io::Result<()> {
         let mut tmp = File::open(self.file_path)?; 
-        tmp.read_to_end(&mut self.source)?;
+        let mut bytes: Vec<u8> = vec![];
+        tmp.read_to_end(&mut bytes)?;
+
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e)   => {
+                let err = format!("{} is not valid UTF-8, byte {} can not be decoded", self.file_path, e.utf8_error().valid_up_to());
+                return Err(make_error(&err));
+            }
+        };
+
+        self.source = text.chars().collect();
         self.size = self.source.len();        
         Ok(())
     }
-/*    
-    pub fn read_block(&mut self) -> io::Result<Token, io::Error> {
-        let mut token: Token = match_lexer_token(self.next());
-        if token.token_type != TokenT::OCURLY__ {
-            let err = format!("READING THE BLOCK FAILED. read_block()\r reason: You need to read in a state of block start {");
-            return Err(make_error(&err));
-        } 
-        
-        let mut opening_loc = token.loc.clone();
-        
-        if token.token_type != TokenT::CCURLY__ && self.is_not_empty() {
-            token = match_lexer_token(self.next());
-        }
-                
-    }
-    
-    pub fn skip_block(&mut self) -> io::Result<(), io::Error> {
-        let mut token: Token = match_lexer_token(self.next());
-        let mut opening_loc = token.loc.clone();
-
-        if token.token_type != TokenT::OCURLY__ {
-            let err = format!("READING THE BLOCK FAILED. read_block()\r reason: You need to read in a state of block start {");
-            return Err(make_error(&err));
-        } 
-        
-        while token.token_type != TokenT::CCURLY__ && self.is_not_empty() {
-            token = match_lexer_token(self.next()); 
-        }
-
-        if token.token_type != TokenT::CCURLY__ {
-            
-            let err = format!("{}:{}:{} unclosed scope", 
-                              self.file_path, 
-                              opening_loc.row, 
-                              opening_loc.col
-                       );
 
-            return Err(make_error(&err));
-        }
-        
-    }
-*/   
     pub fn handle_comment(&mut self,token: &mut Token)  -> Result<(), io::Error> {
         let mut c: char = self.get_current();
         
@@ -152,7 +125,7 @@ impl<'a> KasperLexer<'a> {
         {
             token.token_type = TokenT::COMMENT__; 
 
-            while c != NL {
+            while c != NL && self.is_not_empty() {
                 token.write(c);
                 self.chop();
                 c = self.get_current();
@@ -170,33 +143,32 @@ impl<'a> KasperLexer<'a> {
         self.chop();
     }
 
-    pub fn write_to_special_token(&mut self, token: &mut Token, c: char) {
-        if self.token_table.contains_key(&c) {
-            let t = self.token_table[&c];
-            
-            if t == TokenT::MINUS__ {
-                if self.get_next() == GT {
-                    // ->
-                    token.write(c);
-                    self.chop();
+    // -> => == != <= >= && || .. ..=
+    pub fn write_to_double_token(&mut self, token: &mut Token, c: char) -> bool {
+        let next: char = self.get_next();
+        let pair = format!("{}{}", c, next);
 
-                    self.write_to_token(token, TokenT::THIN_ARROW__, GT);
-                    return;
-                }
-            }
-            
-            if t == TokenT::EQUAL__ {
-                if self.get_next() == GT {
-                    // =>
-                    token.write(c);
-                    self.chop();
+        if let Some(t) = self.double_token_table.get(pair.as_str()).copied() {
+            token.write(c);
+            self.chop();
 
-                    self.write_to_token(token, TokenT::FAT_ARROW__, GT);
-                    
-                    return;
-                }
+            self.write_to_token(token, t, next);
+
+            // ..= is the only three char token.
+            if t == TokenT::RANGE__ && self.get_current() == EQUAL {
+                self.write_to_token(token, TokenT::RANGE_INCL__, EQUAL);
             }
 
+            return true;
+        }
+
+        return false;
+    }
+
+    pub fn write_to_special_token(&mut self, token: &mut Token, c: char) {
+        if self.token_table.contains_key(&c) {
+            let t = self.token_table[&c];
+
                         
             if t != TokenT::DQUOTE__ {
                 self.write_to_token(token, t, c);
@@ -216,6 +188,10 @@ impl<'a> KasperLexer<'a> {
         // it is a known token.
         token.loc.change_loc(self.row, self.col);
         
+        if self.write_to_double_token(token, c) {
+            return Ok(());
+        }
+
         if self.token_table.contains_key(&c) {
             self.write_to_special_token(token, c);
             
@@ -226,7 +202,7 @@ impl<'a> KasperLexer<'a> {
             return Ok(());
         }
 
-        if c.is_ascii_punctuation() {
+        if c.is_ascii_punctuation() && c != UNDERSCORE {
             token.write(c);
             token.token_type = TokenT::NONE__;
             self.chop();
@@ -239,7 +215,7 @@ impl<'a> KasperLexer<'a> {
         
         if self.is_not_empty() {
             
-            let mut c = char::from(self.source[self.cur]);
+            let mut c = self.source[self.cur];
 
             while c.is_ascii_whitespace() && self.is_not_empty() {
                 if c == NL {
@@ -249,7 +225,7 @@ impl<'a> KasperLexer<'a> {
                 self.chop();
                 
                 if self.cur < self.size {
-                    c = char::from(self.source[self.cur]);
+                    c = self.source[self.cur];
                     continue;
                 }
 
@@ -263,10 +239,8 @@ impl<'a> KasperLexer<'a> {
         token.token_type = TokenT::VARNAME__;
         let mut c: char =  self.get_current();
 
-        while c.is_alphanumeric() || c.is_digit(10) && self.is_not_empty() {
-            if c.is_ascii_punctuation() {
-                break;
-            }
+        // Names are letters, digits and _, snake_case works.
+        while (c.is_alphanumeric() || c == UNDERSCORE) && self.is_not_empty() {
             
             if c.is_ascii_whitespace() {
                 break;
@@ -279,39 +253,198 @@ impl<'a> KasperLexer<'a> {
     }
     
     pub fn collect_number(&mut self, token: &mut Token) -> Result<(), io::Error> { 
-        
+        // 12, 1.5, 1e10, 2.5e-3. A dot only belongs to the number when a digit follows it, so 0..10 stays a range.
         token.token_type = TokenT::NUMBER__;
+        let mut exponent = false;
         let mut c: char = self.get_current();
         
-        while self.is_not_empty() && c.is_digit(10) {
+        while self.is_not_empty() {
             
-            if c.is_ascii_punctuation() && c != DOT {
-                break;
-            }
-
-            if c.is_ascii_whitespace() {
-                break;
+            if c.is_ascii_digit() {
+                token.write(c);
+                self.chop();
+                c = self.get_current();
+                continue;
             }
 
-            if c == DOT {
+            if c == DOT && self.get_next().is_ascii_digit() {
                 if token.token_type == TokenT::FLOAT__ {
                     let err = format!("{}:{}:{} synatx error, floats have one dot.", self.file_path, token.loc.row, token.loc.col);
                     return Err(make_error(&err));
                 }
                 
                 token.token_type = TokenT::FLOAT__;
+                token.write(c);
+                self.chop();
+                c = self.get_current();
+                continue;
             }
-            
-            token.write(c);
-            self.chop();
-            c = self.get_current();
+
+            if (c == 'e' || c == 'E') && !exponent {
+                let next = self.get_next();
+                let sign = next == PLUS || next == MINUS;
+                let digits = next.is_ascii_digit() || (sign && self.get_char(self.cur + 2).is_ascii_digit());
+
+                if !digits {
+                    break;
+                }
+
+                exponent = true;
+                token.token_type = TokenT::FLOAT__;
+                token.write(c);
+                self.chop();
+
+                if sign {
+                    token.write(next);
+                    self.chop();
+                }
+
+                c = self.get_current();
+                continue;
+            }
+
+            break;
         }
 
         return Ok(());
     }
 
+    // "text" or "v: {v}", the opening quote is already eaten. Without holes it is a single STRING__,
+    // with holes it is INTERP_BEGIN__ and the rest of the pieces go to pending.
+    pub fn collect_string(&mut self, token: &mut Token) -> Result<(), io::Error> {
+        let mut parts: Vec<Token> = Vec::new();
+        let mut piece = Token::empty();
+        piece.token_type = TokenT::STRING__;
+        piece.loc = token.loc;
+
+        while self.is_not_empty() {
+            let c: char = self.get_current();
+
+            if c == DQUOTE {
+                self.chop();
+
+                if parts.is_empty() {
+                    *token = piece;
+                    return Ok(());
+                }
+
+                if piece.size > 0 {
+                    parts.push(piece);
+                }
+
+                let mut end = Token::empty();
+                end.token_type = TokenT::INTERP_END__;
+                end.loc.change_loc(self.row, self.col - 1);
+                parts.push(end);
+
+                token.token_type = TokenT::INTERP_BEGIN__;
+                self.pending.extend(parts);
+                return Ok(());
+            }
+
+            if c == ESCAPE {
+                self.chop();
+
+                let escaped: char = match self.get_current() {
+                    '\"'  => DQUOTE,
+                    '\'' => SQUOTE,
+                    '\\' => ESCAPE,
+                    'n'  => NL,
+                    't'  => TAB,
+                    'r'  => RE,
+                    '0'  => NULLC,
+                    '{'  => OCURLY,
+                    e    => {
+                        let err = format!("{}:{}:{} unknown escape sequence \\{}", self.file_path, self.row, self.col - 1, e);
+                        return Err(make_error(&err));
+                    }
+                };
+
+                piece.write(escaped);
+                self.chop();
+                continue;
+            }
+
+            if c == OCURLY {
+                if piece.size > 0 {
+                    parts.push(piece);
+                }
+
+                self.collect_hole(&mut parts)?;
+
+                piece = Token::empty();
+                piece.token_type = TokenT::STRING__;
+                piece.loc.change_loc(self.row, self.col);
+                continue;
+            }
+
+            piece.write(c);
+            self.chop();
+        }
+
+        // We did not find the terminating quote ?
+        let mut err_text = format!("{}:{}:{} Interminated string literal.", self.file_path, token.loc.row, token.loc.col);
+        err_text    += "Add \" to terminate the string..";
+        return Err(make_error(&err_text));
+    }
+
+    // The tokens of one { expr } hole of a string, up to its matching }.
+    pub fn collect_hole(&mut self, parts: &mut Vec<Token>) -> Result<(), io::Error> {
+        let mut open = Token::empty();
+        open.loc.change_loc(self.row, self.col);
+        self.write_to_token(&mut open, TokenT::OCURLY__, OCURLY);
+
+        let loc = open.loc;
+        let mut depth: usize = 0;
+        parts.push(open);
+
+        loop {
+            let token = self.next()?;
+
+            match token.token_type {
+                TokenT::OCURLY__ => depth += 1,
+                TokenT::CCURLY__ if depth == 0 => {
+                    parts.push(token);
+                    return Ok(());
+                },
+                TokenT::CCURLY__ => depth -= 1,
+                TokenT::NL__ | TokenT::COMMENT__ => {
+                    let err = format!("{}:{}:{} unclosed {{ in a string, add }} to close it", self.file_path, loc.row, loc.col);
+                    return Err(make_error(&err));
+                },
+                TokenT::NONE__ => {
+                    if token.size == 0 && !self.is_not_empty() {
+                        let err = format!("{}:{}:{} unclosed {{ in a string, add }} to close it", self.file_path, loc.row, loc.col);
+                        return Err(make_error(&err));
+                    }
+
+                    return Err(self.unrecognized(token));
+                },
+                _ => {},
+            }
+
+            parts.push(token);
+        }
+    }
+
+    pub fn unrecognized(&mut self, token: Token) -> io::Error {
+        let value = if token.size == 0 { self.get_current().to_string() } else { token.value };
+        let err = format!("{}:{}:{} Syntax error, unrecognized token {}",
+                           self.file_path,
+                           token.loc.row,
+                           token.loc.col,
+                           value
+                        );
+
+        return make_error(&err);
+    }
+
     pub fn next(&mut self) -> Result<Token, io::Error> {
         
+        if let Some(token) = self.pending.pop_front() {
+            return Ok(token);
+        }
+
         self.trim_spaces_left();
     
         let mut token = Token::empty();
@@ -322,66 +455,11 @@ impl<'a> KasperLexer<'a> {
         match res {
             Err(e) => return Err(e),
             Ok(()) => {
-                let mut c: char = self.get_current();
+                let c: char = self.get_current();
                 
                 if token.token_type == TokenT::DQUOTE__ {
-                    let mut prev: char = '.';
-                    
-                    while self.is_not_empty() {
-                        prev = self.get_prev();
-
-                        if prev == ESCAPE {
-                            match c {
-                                '\"' => {
-                                    token.write(DQUOTE);
-                                },
-                                '\'' => {
-                                    token.write(SQUOTE);
-
-                                },
-                                '\\' => {
-                                    token.write(ESCAPE);
-                                },
-                                'n' => {
-                                    token.write(NL);
-                                },
-                                't' => {
-                                    token.write(TAB);
-                                },
-                                'r' => {
-                                    token.write(RE);
-                                },
-                                '0' => {
-                                    token.write(NULLC);
-                                },
-                                _ => {
-                                    todo!("Unreachable!, if the escape sequence is not completed.");
-                                }
-                            }
-                            
-                            self.chop();
-                            c = self.get_current();    
-                        }
-
-                        if c == DQUOTE {
-                            token.token_type = TokenT::STRING__;
-                            self.chop();
-                            return Ok(token);
-                        }
-
-                        if c != ESCAPE {
-                            token.write(c);
-                        }
-                        
-                        self.chop();
-                        prev = c.clone();
-                        c    = self.get_current();
-                    }
-                    
-                    // We did not find the terminating quote ?
-                    let mut err_text = format!("{}:{}:{} Interminated string literal prev: {} Current: {}.", self.file_path, token.loc.row, token.loc.col, prev, c);
-                    err_text    += &format!("Add \" to terminate the string..");
-                    return Err(make_error(&err_text));
+                    self.collect_string(&mut token)?;
+                    return Ok(token);
                 }
             
                 if token.size > 0 { 
@@ -390,7 +468,7 @@ impl<'a> KasperLexer<'a> {
                  
                 token.loc.change_loc(self.row, self.col);
                 
-                if c.is_alphabetic() {
+                if c.is_alphabetic() || c == UNDERSCORE {
                     self.collect_str(&mut token); // VARNAME__
                     
                     match &token.value as &str {
@@ -398,10 +476,22 @@ impl<'a> KasperLexer<'a> {
                             token.token_type = TokenT::KEY_WORD_DEFINE__;
                             return Ok(token);
                         },
-                        INT => {
+                        CONST => {
+                            token.token_type = TokenT::CONST__;
+                            return Ok(token);
+                        },
+                        INT | INT_32 => {
                             token.token_type = TokenT::INT_T;
                             return Ok(token);
                         },
+                        INT_8 => {
+                            token.token_type = TokenT::INT_T_8;
+                            return Ok(token);
+                        },
+                        INT_16 => {
+                            token.token_type = TokenT::INT_T_16;
+                            return Ok(token);
+                        },
                         STRING => {
                             token.token_type = TokenT::STRING_T;
                             return Ok(token);
@@ -410,10 +500,46 @@ impl<'a> KasperLexer<'a> {
                             token.token_type = TokenT::INT_T_64;
                             return Ok(token);
                         },
+                        INT_128 => {
+                            token.token_type = TokenT::INT_T_128;
+                            return Ok(token);
+                        },
+                        UINT_8 => {
+                            token.token_type = TokenT::UINT_T_8;
+                            return Ok(token);
+                        },
+                        UINT_16 => {
+                            token.token_type = TokenT::UINT_T_16;
+                            return Ok(token);
+                        },
+                        UINT_32 => {
+                            token.token_type = TokenT::UINT_T_32;
+                            return Ok(token);
+                        },
+                        UINT_64 => {
+                            token.token_type = TokenT::UINT_T_64;
+                            return Ok(token);
+                        },
+                        UINT_128 => {
+                            token.token_type = TokenT::UINT_T_128;
+                            return Ok(token);
+                        },
                         BOOL => {
                             token.token_type = TokenT::BOOL_T;
                             return Ok(token);
                         },
+                        MAP => {
+                            token.token_type = TokenT::MAP_T;
+                            return Ok(token);
+                        },
+                        FLOAT | FLOAT_64 => {
+                            token.token_type = TokenT::FLOAT_T;
+                            return Ok(token);
+                        },
+                        FLOAT_32 => {
+                            token.token_type = TokenT::FLOAT_T_32;
+                            return Ok(token);
+                        },
                         BOOL_TRUE => {
                             token.token_type = TokenT::BOOL_TRUE__;
                             return Ok(token);
@@ -433,26 +559,54 @@ impl<'a> KasperLexer<'a> {
                             return Ok(token);
  
                         },
-                        COMP_EQ => {
-                            token.token_type = TokenT::COMP_EQ__;
+                        PROCC => {
+                            token.token_type = TokenT::KEY_WORD_PROCESS__;
                             return Ok(token);
- 
                         },
-                        COMP_NOT_EQ => {
-                            token.token_type = TokenT::COMP_NOT_EQ__;
+                        RETURN => {
+                            token.token_type = TokenT::RETURN__;
                             return Ok(token);
- 
                         },
-                        COMP_LT_EQ => {
-                            token.token_type = TokenT::COMP_LT_EQ__;
+                        WHILE => {
+                            token.token_type = TokenT::WHILE__;
                             return Ok(token);
- 
                         },
-                        COMP_GT_EQ => {
-                            token.token_type = TokenT::COMP_GT_EQ__;
+                        BREAK => {
+                            token.token_type = TokenT::BREAK__;
                             return Ok(token);
- 
-                        }
+                        },
+                        CONTINUE => {
+                            token.token_type = TokenT::CONTINUE__;
+                            return Ok(token);
+                        },
+                        FOR => {
+                            token.token_type = TokenT::FOR__;
+                            return Ok(token);
+                        },
+                        IN => {
+                            token.token_type = TokenT::IN__;
+                            return Ok(token);
+                        },
+                        STEP => {
+                            token.token_type = TokenT::STEP__;
+                            return Ok(token);
+                        },
+                        AS => {
+                            token.token_type = TokenT::AS__;
+                            return Ok(token);
+                        },
+                        STRUCT => {
+                            token.token_type = TokenT::STRUCT__;
+                            return Ok(token);
+                        },
+                        ENUM => {
+                            token.token_type = TokenT::ENUM__;
+                            return Ok(token);
+                        },
+                        MATCH => {
+                            token.token_type = TokenT::MATCH__;
+                            return Ok(token);
+                        },
                         _ => {
                             if self.get_current() == OPAR {
                                 token.token_type = TokenT::FUNC_CALL__;
@@ -463,7 +617,7 @@ impl<'a> KasperLexer<'a> {
                     }
                 }
                 
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     match self.collect_number(&mut token) {
                         Ok(())   => return Ok(token),
                         Err(e) => return Err(e),
@@ -474,19 +628,29 @@ impl<'a> KasperLexer<'a> {
             }
         }
     }
-}
 
+    // Reads the whole source into a token stream, comments are dropped.
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, io::Error> {
+        let mut tokens: Vec<Token> = Vec::new();
+
+        while self.is_not_empty() || !self.pending.is_empty() {
+            let token = self.next()?;
+
+            if token.token_type == TokenT::COMMENT__ {
+                continue;
+            }
+
+            if token.token_type == TokenT::NONE__ {
+                if token.size == 0 && !self.is_not_empty() {
+                    break; // Trailing white space.
+                }
 
-pub fn match_lexer_token(res: Result<Token, io::Error>) -> Token {
-    match res {
-        Ok(token) => {
-            return token;        
-        },
-        Err(e) => {
-            println!("{}", e);
-            exit(1);
+                return Err(self.unrecognized(token));
+            }
+
+            tokens.push(token);
         }
-    }
 
+        return Ok(tokens);
+    }
 }
-
@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 mod lexer;
 mod kasper_parser;
 mod enums;
@@ -5,42 +7,126 @@ mod token;
 mod stack;
 mod util;
 mod condition;
+mod match_parser;
 mod expr_parser;
+mod ast;
+mod evaluator;
+mod arith;
+mod cast;
+mod sequence;
+mod builtins;
+mod place;
+mod structs;
+mod matching;
+mod typeck;
+mod types;
+mod value;
 
 use crate::lexer::*;
 use crate::kasper_parser::KasperParser as KParser;
+use crate::evaluator::Evaluator;
+use crate::arith::Overflow;
 use std::env;
 use std::io;
+use std::thread;
+
+// The parser recurses once per nesting and the evaluator once per call, this is the stack they run on.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
+fn usage(program: &str) {
+    println!("---------------------------------");
+    println!("The File path was not provided.");
+    println!("Usage: {} [options] <path>", program);
+    println!("Options:");
+    println!("    --overflow=trap|wrap|saturate   integer overflow policy, trap by default.");
+    println!("    --warn-shadow                   warn when a name hides one of an outer scope.");
+    println!("---------------------------------");
+}
 
 #[allow(unused_variables)]
 fn main() -> Result<(), io::Error> {
    
     let args: Vec<String> = env::args().collect();
     let program = &args[0];
-    
-    if args.len() < 2 {
-        println!("---------------------------------");
-        println!("The File path was not provided.");
-        println!("Usage: {} <path>", program);
-        println!("---------------------------------");
-        return Ok(());
+    let mut path: Option<&String> = None;
+    let mut overflow = Overflow::Trap;
+    let mut warn_shadow = false;
+
+    for arg in &args[1..] {
+        if let Some(policy) = arg.strip_prefix("--overflow=") {
+            match Overflow::from_str(policy) {
+                Some(o) => overflow = o,
+                None => {
+                    println!("unknown overflow policy {}, expected trap, wrap or saturate.", policy);
+                    return Ok(());
+                }
+            }
+
+            continue;
+        }
+
+        if arg == "--warn-shadow" {
+            warn_shadow = true;
+            continue;
+        }
+
+        if arg.starts_with("--") {
+            println!("unknown option {}", arg);
+            usage(program);
+            return Ok(());
+        }
+
+        path = Some(arg);
     }
     
-    let src = &args[1];
-    // Plugin the parser with the lexer. 
-    let lex: KasperLexer = KasperLexer::new(&src);
-    let mut parser: KParser = KParser::new(lex);
-    parser.lexer.read()?;
+    let src = match path {
+        Some(src) => src,
+        None => {
+            usage(program);
+            return Ok(());
+        }
+    };
+
+    // Deeply nested source would overflow the main thread's stack, lexing, parsing and evaluating get their own.
+    return thread::scope(|scope| {
+        let runner = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || run(src, overflow, warn_shadow))?;
+
+        match runner.join() {
+            Ok(result) => return result,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    });
+}
+
+fn run(src: &str, overflow: Overflow, warn_shadow: bool) -> Result<(), io::Error> {
+    // Lex the whole source, parse it into an ast then evaluate the tree.
+    let mut lex: KasperLexer = KasperLexer::new(src);
+    lex.read()?;
     
-    while parser.lexer.is_not_empty() {
-       match parser.parse_lexer() {
-            Ok(()) => continue,
-            Err(e) => {
-                println!("{}", e);
-                return Ok(());
-            }
+    let tokens = match lex.tokenize() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
         }
+    };
+
+    let mut parser: KParser = KParser::new(src, tokens);
+    let ast = match parser.parse_program() {
+        Ok(ast) => ast,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+
+    let mut evaluator = Evaluator::new(src);
+    evaluator.overflow = overflow;
+    evaluator.warn_shadow = warn_shadow;
+    if let Err(e) = evaluator.run(&ast) {
+        println!("{}", e);
     }
 
     return Ok(());
@@ -62,8 +148,7 @@ fn test_lexer() -> Result<(), io::Error> {
     }
     
     let src = &args[1];
-    // Plugin the parser with the lexer. 
-    let mut lex: KasperLexer = KasperLexer::new(&src);
+    let mut lex: KasperLexer = KasperLexer::new(src);
     lex.read()?;
         
     while lex.is_not_empty() {
@@ -81,4 +166,3 @@ fn test_lexer() -> Result<(), io::Error> {
    }
     return Ok(());
 }
-
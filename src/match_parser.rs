@@ -0,0 +1,112 @@
+// Match: match expr { pattern => expr, ... }, the arms are tried from the top.
+use std::io;
+use crate::ast::*;
+use crate::enums::{ TokenT, WILDCARD };
+use crate::kasper_parser::KasperParser;
+use crate::token::Token;
+
+impl<'a> KasperParser<'a> {
+
+    // A match statement can have { ... } arms, a match expression needs a value from each arm.
+    // The match keyword was eaten by the caller.
+    pub fn parse_match(&mut self, token: &Token, statement: bool) -> Result<Match, io::Error> {
+        // The { after the subject opens the arms, x { is not a struct literal.
+        let no_structs  = self.no_structs;
+        self.no_structs = true;
+        let subject = self.parse_expression()?;
+        self.no_structs = no_structs;
+
+        self.expect(TokenT::OCURLY__, "{")?;
+
+        let mut arms: Vec<Arm> = Vec::new();
+        let mut wildcard = false;
+
+        loop {
+            self.skip_new_lines();
+
+            if self.peek_type() == TokenT::CCURLY__ {
+                self.advance();
+                break;
+            }
+
+            let start = self.peek().clone();
+
+            if wildcard {
+                return Err(self.error_at(&start, "this arm is never reached, the _ above it takes everything"));
+            }
+
+            let pattern = self.parse_pattern()?;
+            wildcard = matches!(pattern, Pattern::Wildcard);
+
+            self.expect(TokenT::FAT_ARROW__, "=>")?;
+            self.skip_new_lines();
+
+            let body = if statement && self.peek_type() == TokenT::OCURLY__ {
+                ArmBody::Block(self.parse_block()?)
+            } else {
+                ArmBody::Expr(self.parse_expression()?)
+            };
+
+            arms.push(Arm { pattern, body, loc: start.loc });
+
+            match self.peek_type() {
+                TokenT::COMA__ | TokenT::NL__ => { self.advance(); },
+                TokenT::CCURLY__ => {},
+                _ => return Err(self.unexpected(self.peek(), ", or }")),
+            }
+        }
+
+        if arms.is_empty() {
+            return Err(self.error_at(token, "a match needs at least one arm"));
+        }
+
+        return Ok(Match { subject, arms });
+    }
+
+    pub fn parse_pattern(&mut self) -> Result<Pattern, io::Error> {
+        // _ / A / B(x, _) / a literal
+        let token = self.peek().clone();
+
+        match token.token_type {
+            TokenT::VARNAME__ if token.value == WILDCARD => {
+                self.advance();
+                return Ok(Pattern::Wildcard);
+            },
+            TokenT::VARNAME__ => {
+                self.advance();
+
+                if self.peek_type() != TokenT::OPAR__ {
+                    return Ok(Pattern::Variant { name: token.value, bindings: Vec::new() });
+                }
+
+                self.advance();
+            },
+            TokenT::FUNC_CALL__ => { self.advance(); }, // The lexer ate the (.
+            TokenT::NUMBER__ | TokenT::FLOAT__ | TokenT::STRING__ | TokenT::BOOL_TRUE__ | TokenT::BOOL_FALSE__ | TokenT::MINUS__ => {
+                return Ok(Pattern::Literal(self.parse_unary()?));
+            },
+            _ => return Err(self.unexpected(&token, "a pattern")),
+        }
+
+        let mut bindings: Vec<String> = Vec::new();
+
+        loop {
+            let name = self.expect(TokenT::VARNAME__, "a name to bind")?;
+
+            if name.value != WILDCARD && bindings.contains(&name.value) {
+                return Err(self.error_at(&name, &format!("{} is bound twice in this pattern", name.value)));
+            }
+
+            bindings.push(name.value);
+
+            match self.peek_type() {
+                TokenT::COMA__ => { self.advance(); },
+                TokenT::CPAR__ => {
+                    self.advance();
+                    return Ok(Pattern::Variant { name: token.value, bindings });
+                },
+                _ => return Err(self.unexpected(self.peek(), ", or )")),
+            }
+        }
+    }
+}
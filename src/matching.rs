@@ -0,0 +1,185 @@
+// Enums and match. A value of an enum is one of its variants, match takes it apart.
+use std::io;
+use crate::ast::{ Arm, ArmBody, BinOp, EnumDecl, Expr, ExprKind, Match, Pattern };
+use crate::enums::WILDCARD;
+use crate::evaluator::{ Evaluator, Flow };
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+// The names a pattern bound and the payload they hold.
+type Bindings = Vec<(String, Value)>;
+
+impl<'a> Evaluator<'a> {
+
+    // A / B(expr, ...), the payload takes the types the variant declares.
+    pub fn construct(&mut self, name: &str, args: &[Expr], loc: &Location) -> Result<Value, io::Error> {
+        let decl: &'a EnumDecl = self.variants[name];
+        let variant = match decl.variants.iter().find(|v| v.name == name) {
+            Some(variant) => variant,
+            None          => unreachable!("{} is registered as a variant of {}", name, decl.name),
+        };
+
+        if args.len() != variant.fields.len() {
+            let err = format!("{} holds {} values but got {}", name, variant.fields.len(), args.len());
+            return Err(self.error_at(loc, &err));
+        }
+
+        let mut payload: Vec<Value> = Vec::new();
+
+        for (i, (arg, t)) in args.iter().zip(&variant.fields).enumerate() {
+            let v     = self.eval_with(arg, Some(t))?;
+            let found = v.get_type();
+
+            match self.coerce(v, t, &arg.loc) {
+                Ok(v)  => payload.push(v),
+                Err(_) => {
+                    let err = format!("value {} of {} expects {} but found {}", i + 1, name, t, found);
+                    return Err(self.error_at(&arg.loc, &err));
+                }
+            }
+        }
+
+        return Ok(Value::Enum(decl.name.clone(), name.to_string(), payload));
+    }
+
+    // match as a statement, the arm runs in its own scope with the names its pattern bound.
+    pub fn exec_match(&mut self, m: &Match, loc: &Location) -> Result<Flow, io::Error> {
+        let subject = self.eval(&m.subject)?;
+        let (arm, bindings) = self.select_arm(m, &subject, loc)?;
+
+        self.stack.push_scope();
+
+        for (name, value) in bindings {
+            self.stack.define(&name, value.get_type(), value, arm.loc);
+        }
+
+        let (flow, end) = match &arm.body {
+            ArmBody::Block(block) => (self.exec_block(block), block.end),
+            ArmBody::Expr(expr)   => (self.exec_expr(expr).map(|_| Flow::Next), expr.loc),
+        };
+
+        self.stack.pop_scope(end);
+        return flow;
+    }
+
+    // match as an expression, its value is the value of the arm that was taken.
+    pub fn eval_match(&mut self, m: &Match, hint: Option<&Type>, loc: &Location) -> Result<Value, io::Error> {
+        let subject = self.eval(&m.subject)?;
+        let (arm, bindings) = self.select_arm(m, &subject, loc)?;
+
+        let expr = match &arm.body {
+            ArmBody::Expr(expr)  => expr,
+            ArmBody::Block(_)    => unreachable!("the parser only gives a match expression expression arms"),
+        };
+
+        self.stack.push_scope();
+
+        for (name, value) in bindings {
+            self.stack.define(&name, value.get_type(), value, arm.loc);
+        }
+
+        let v = self.eval_with(expr, hint);
+        self.stack.pop_scope(expr.loc);
+        return v;
+    }
+
+    // The first arm whose pattern takes the value, with the payload it binds. _ binds nothing.
+    pub fn select_arm<'m>(&mut self, m: &'m Match, subject: &Value, loc: &Location) -> Result<(&'m Arm, Bindings), io::Error> {
+        let t = subject.get_type();
+        self.check_exhaustive(m, &t, loc)?;
+
+        for arm in &m.arms {
+            match &arm.pattern {
+                Pattern::Wildcard => return Ok((arm, Vec::new())),
+                Pattern::Variant { name, bindings } => {
+                    if let Value::Enum(_, variant, payload) = subject {
+                        if variant == name {
+                            let bound = bindings.iter().zip(payload).filter(|(b, _)| *b != WILDCARD);
+                            return Ok((arm, bound.map(|(b, v)| (b.clone(), v.clone())).collect()));
+                        }
+                    }
+                },
+                Pattern::Literal(expr) => {
+                    let v = self.eval_with(expr, Some(&t))?;
+
+                    if self.compare(BinOp::Eq, subject, &v, &expr.loc)? {
+                        return Ok((arm, Vec::new()));
+                    }
+                },
+            }
+        }
+
+        unreachable!("an exhaustive match takes every value");
+    }
+
+    // Every variant of an enum needs an arm, any other value needs a _ unless it is a bool with both arms.
+    // The patterns are checked against the enum every time, not only the arm that is taken.
+    pub fn check_exhaustive(&self, m: &Match, t: &Type, loc: &Location) -> Result<(), io::Error> {
+        let decl: &'a EnumDecl = match t {
+            Type::Named(name) if self.enums.contains_key(name) => self.enums[name],
+            _ => {
+                let mut seen: Vec<bool> = Vec::new();
+
+                for arm in &m.arms {
+                    match &arm.pattern {
+                        Pattern::Wildcard => return Ok(()),
+                        Pattern::Variant { name, .. } => {
+                            let err = format!("{} is a variant but the match is on {}", name, t);
+                            return Err(self.error_at(&arm.loc, &err));
+                        },
+                        Pattern::Literal(Expr { kind: ExprKind::Bool(b), .. }) => seen.push(*b),
+                        Pattern::Literal(_) => {},
+                    }
+                }
+
+                if seen.contains(&true) && seen.contains(&false) {
+                    return Ok(());
+                }
+
+                let err = format!("match on {} is not exhaustive, add a _ arm for the values no other arm takes", t);
+                return Err(self.error_at(loc, &err));
+            }
+        };
+
+        let mut covered: Vec<&str> = Vec::new();
+
+        for arm in &m.arms {
+            let (name, bindings) = match &arm.pattern {
+                Pattern::Wildcard => return Ok(()),
+                Pattern::Variant { name, bindings } => (name, bindings),
+                Pattern::Literal(_) => {
+                    let err = format!("a {} is matched by its variants, not by a value", t);
+                    return Err(self.error_at(&arm.loc, &err));
+                },
+            };
+
+            let variant = match decl.variants.iter().find(|v| v.name == *name) {
+                Some(variant) => variant,
+                None => return Err(self.error_at(&arm.loc, &format!("{} is not a variant of {}", name, t))),
+            };
+
+            // A without bindings takes the variant whatever it holds.
+            if !bindings.is_empty() && bindings.len() != variant.fields.len() {
+                let err = format!("{} holds {} values but the pattern binds {}", name, variant.fields.len(), bindings.len());
+                return Err(self.error_at(&arm.loc, &err));
+            }
+
+            if covered.contains(&name.as_str()) {
+                return Err(self.error_at(&arm.loc, &format!("this arm is never reached, {} is matched above it", name)));
+            }
+
+            covered.push(name);
+        }
+
+        let missing: Vec<&str> = decl.variants.iter().map(|v| v.name.as_str()).filter(|v| !covered.contains(v)).collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let (verb, arms) = if missing.len() == 1 { ("is", "an arm for it") } else { ("are", "arms for them") };
+        let err = format!("match on {} is not exhaustive, {} {} not matched, add {} or a _ arm", t, missing.join(", "), verb, arms);
+        return Err(self.error_at(loc, &err));
+    }
+}
@@ -0,0 +1,333 @@
+// Places, a variable and the path of indexes, keys and fields into it that an assignment, push or remove writes to.
+// Reads of xs[i], m[k] and p.x walk the same path by reference, so only the part that is read is copied.
+use std::io;
+use crate::ast::{ Expr, ExprKind };
+use crate::enums::{ PUSH, REMOVE };
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::util::make_error_at;
+use crate::value::Value;
+
+// One step from a value into a part of it.
+pub enum Step {
+    Index(i128, Location),
+    Key(Value, Location),
+    Field(String, Location),
+}
+
+// Where a read comes from: a variable and the steps into it, or a value that had to be computed.
+pub enum Source {
+    Place(String, Location, Vec<Step>),
+    Value(Value),
+}
+
+impl<'a> Evaluator<'a> {
+
+    // Walks xs[i][j] down to its variable, the indexes are evaluated on the way, outermost first.
+    // What is being indexed decides whether the step is a position or a key.
+    pub fn resolve_place(&mut self, expr: &Expr, steps: &mut Vec<Step>) -> Result<(String, Location), io::Error> {
+        match &expr.kind {
+            ExprKind::Var(name) => return Ok((name.clone(), expr.loc)),
+            ExprKind::Index { target, index } => {
+                let (name, loc) = self.resolve_place(target, steps)?;
+
+                if let Type::Map(k, _) = self.place_mut(&name, &loc, steps)?.get_type() {
+                    let key = self.eval_key(&k, index)?;
+                    steps.push(Step::Key(key, expr.loc));
+                } else {
+                    let i = self.eval_position(index)?;
+                    steps.push(Step::Index(i, expr.loc));
+                }
+
+                return Ok((name, loc));
+            },
+            ExprKind::Field { target, name } => {
+                let place = self.resolve_place(target, steps)?;
+                steps.push(Step::Field(name.clone(), expr.loc));
+                return Ok(place);
+            },
+            _ => return Err(self.error_at(&expr.loc, "expected a variable or an element of one")),
+        }
+    }
+
+    // The value of a Var, Index or Field expression, only the part it names is cloned.
+    pub fn read(&mut self, expr: &Expr) -> Result<Value, io::Error> {
+        match self.resolve_read(expr)? {
+            Source::Place(name, loc, steps) => return Ok(self.place_ref(&name, &loc, &steps)?.clone()),
+            Source::Value(v)                => return Ok(v),
+        }
+    }
+
+    // Like resolve_place, the indexes are evaluated outermost first. What is not a variable, or a character of a string, is computed.
+    pub fn resolve_read(&mut self, expr: &Expr) -> Result<Source, io::Error> {
+        match &expr.kind {
+            ExprKind::Var(name) if self.stack.get(name).is_some() => return Ok(Source::Place(name.clone(), expr.loc, Vec::new())),
+            ExprKind::Index { target, index } => {
+                let (name, loc, mut steps) = match self.resolve_read(target)? {
+                    Source::Place(name, loc, steps) => (name, loc, steps),
+                    Source::Value(v)                => return Ok(Source::Value(self.index_value(v, index, &expr.loc)?)),
+                };
+
+                match self.place_ref(&name, &loc, &steps)?.get_type() {
+                    Type::Map(k, _) => {
+                        let key = self.eval_key(&k, index)?;
+                        steps.push(Step::Key(key, expr.loc));
+                    },
+                    Type::Array(_) => {
+                        let i = self.eval_position(index)?;
+                        steps.push(Step::Index(i, expr.loc));
+                    },
+                    _ => {
+                        // A character of a string is built, it is not in the string as a Value.
+                        let i = self.eval_position(index)?;
+                        return Ok(Source::Value(self.index(self.place_ref(&name, &loc, &steps)?, i, &expr.loc)?));
+                    },
+                }
+
+                return Ok(Source::Place(name, loc, steps));
+            },
+            ExprKind::Field { target, name } => {
+                match self.resolve_read(target)? {
+                    Source::Place(var, loc, mut steps) => {
+                        steps.push(Step::Field(name.clone(), expr.loc));
+                        return Ok(Source::Place(var, loc, steps));
+                    },
+                    Source::Value(v) => return Ok(Source::Value(self.field(v, name, &expr.loc)?)),
+                }
+            },
+            _ => return Ok(Source::Value(self.eval(expr)?)),
+        }
+    }
+
+    pub fn source_ref<'s>(&'s self, source: &'s Source) -> Result<&'s Value, io::Error> {
+        match source {
+            Source::Place(name, loc, steps) => return self.place_ref(name, loc, steps),
+            Source::Value(v)                => return Ok(v),
+        }
+    }
+
+    // place_mut for reading.
+    pub fn place_ref(&self, name: &str, loc: &Location, steps: &[Step]) -> Result<&Value, io::Error> {
+        let mut value: &Value = match self.stack.get(name) {
+            Some(var) => &var.value,
+            None      => return Err(self.undefined(name, loc)),
+        };
+
+        for step in steps {
+            match (step, value) {
+                (Step::Index(i, loc), Value::Array(_, items)) => {
+                    match usize::try_from(*i) {
+                        Ok(i) if i < items.len() => value = &items[i],
+                        _ => {
+                            let err = format!("index {} is out of bounds, the {} has {} elements", i, value.get_type(), items.len());
+                            return Err(self.error_at(loc, &err));
+                        }
+                    }
+                },
+                (Step::Key(key, loc), Value::Map(_, _, entries)) => {
+                    match entries.get(key) {
+                        Some(v) => value = v,
+                        None    => return Err(self.missing_key(key, loc)),
+                    }
+                },
+                (Step::Field(name, loc), _) => {
+                    let found = match value {
+                        Value::Struct(_, fields) => fields.iter().find(|(f, _)| f == name),
+                        _                        => None,
+                    };
+
+                    match found {
+                        Some((_, v)) => value = v,
+                        None         => return Err(self.error_at(loc, &format!("{} has no field {}", value.get_type(), name))),
+                    }
+                },
+                (Step::Index(_, loc), _) | (Step::Key(_, loc), _) => {
+                    return Err(self.error_at(loc, &format!("can not index {}", value.get_type())));
+                },
+            }
+        }
+
+        return Ok(value);
+    }
+
+    pub fn place_mut(&mut self, name: &str, loc: &Location, steps: &[Step]) -> Result<&mut Value, io::Error> {
+        if self.stack.get(name).is_none() {
+            return Err(self.undefined(name, loc));
+        }
+
+        let file_path = self.file_path;
+        let mut value: &mut Value = match self.stack.get_mut(name) {
+            Some(var) => &mut var.value,
+            None      => unreachable!(),
+        };
+
+        for step in steps {
+            match step {
+                Step::Index(i, loc) => {
+                    let t = value.get_type();
+
+                    match value {
+                        Value::Array(_, items) => {
+                            let len = items.len();
+
+                            match usize::try_from(*i) {
+                                Ok(i) if i < len => value = &mut items[i],
+                                _ => {
+                                    let err = format!("index {} is out of bounds, the {} has {} elements", i, t, len);
+                                    return Err(make_error_at(file_path, loc, &err));
+                                }
+                            }
+                        },
+                        Value::Str(_) => {
+                            return Err(make_error_at(file_path, loc, "a string does not change in place, build a new one with + or replace"));
+                        },
+                        _ => return Err(make_error_at(file_path, loc, &format!("can not index {}", t))),
+                    }
+                },
+                Step::Key(key, loc) => {
+                    let found = match value {
+                        Value::Map(_, _, entries) => entries.get_mut(key),
+                        _                         => None,
+                    };
+
+                    match found {
+                        Some(v) => value = v,
+                        None => {
+                            let err = format!("key {} is not in the map", key.quoted());
+                            return Err(make_error_at(file_path, loc, &err));
+                        }
+                    }
+                },
+                Step::Field(name, loc) => {
+                    let t = value.get_type();
+                    let found = match value {
+                        Value::Struct(_, fields) => fields.iter_mut().find(|(f, _)| f == name),
+                        _                        => None,
+                    };
+
+                    match found {
+                        Some((_, v)) => value = v,
+                        None         => return Err(make_error_at(file_path, loc, &format!("{} has no field {}", t, name))),
+                    }
+                },
+            }
+        }
+
+        return Ok(value);
+    }
+
+    // xs[i] = expr, the indexes are evaluated before the value.
+    // m[k] = expr inserts k when it is not in the map yet, so the last key is not looked up.
+    pub fn assign_to(&mut self, target: &Expr, value: &Expr) -> Result<(), io::Error> {
+        let mut steps: Vec<Step> = Vec::new();
+        let (name, loc) = self.resolve_place(target, &mut steps)?;
+
+        let inserts   = matches!(steps.last(), Some(Step::Key(..)));
+        let slot_type = match self.place_mut(&name, &loc, &steps[..steps.len() - inserts as usize])?.get_type() {
+            Type::Map(_, v) if inserts => *v,
+            t                          => t,
+        };
+
+        let v     = self.eval_with(value, Some(&slot_type))?;
+        let found = v.get_type();
+
+        let v = match self.coerce(v, &slot_type, &value.loc) {
+            Ok(v)  => v,
+            Err(_) => {
+                let slot = match steps.last() {
+                    Some(Step::Field(field, _)) => format!("field {} of {}", field, name),
+                    _                           => format!("an element of {}", name),
+                };
+
+                let err = format!("can not assign {} to {}, it holds {}", found, slot, slot_type);
+                return Err(self.error_at(&value.loc, &err));
+            }
+        };
+
+        // The value may have changed the array, the place is looked up again.
+        if let Some(Step::Key(key, _)) = steps.pop_if(|step| matches!(step, Step::Key(..))) {
+            if let Value::Map(_, _, entries) = self.place_mut(&name, &loc, &steps)? {
+                entries.insert(key, v);
+            }
+
+            return Ok(());
+        }
+
+        *self.place_mut(&name, &loc, &steps)? = v;
+        return Ok(());
+    }
+
+    // push(xs, v) adds v at the end of xs, pop(xs) takes the last element off and returns it.
+    pub fn push_pop(&mut self, name: &str, args: &[Expr], loc: &Location) -> Result<Option<Value>, io::Error> {
+        let arity = if name == PUSH { 2 } else { 1 };
+
+        if args.len() != arity {
+            let err = format!("{} expects {} arguments but got {}", name, arity, args.len());
+            return Err(self.error_at(loc, &err));
+        }
+
+        let mut steps: Vec<Step> = Vec::new();
+        let (var, var_loc) = self.resolve_place(&args[0], &mut steps)?;
+
+        let element_type = match self.place_mut(&var, &var_loc, &steps)?.get_type() {
+            Type::Array(t) => *t,
+            t => {
+                let err = format!("argument 1 of {} expects an array but found {}", name, t);
+                return Err(self.error_at(&args[0].loc, &err));
+            }
+        };
+
+        if name == PUSH {
+            let v = self.eval_with(&args[1], Some(&element_type))?;
+            let v = self.coerce(v, &element_type, &args[1].loc)?;
+
+            if let Value::Array(_, items) = self.place_mut(&var, &var_loc, &steps)? {
+                items.push(v);
+            }
+
+            return Ok(None);
+        }
+
+        let popped = match self.place_mut(&var, &var_loc, &steps)? {
+            Value::Array(_, items) => items.pop(),
+            _                      => None,
+        };
+
+        match popped {
+            Some(v) => return Ok(Some(v)),
+            None    => return Err(self.error_at(loc, "pop from an empty array")),
+        }
+    }
+
+    // remove(m, k) takes k out of m and returns its value, the other keys keep their order.
+    pub fn remove(&mut self, args: &[Expr], loc: &Location) -> Result<Option<Value>, io::Error> {
+        if args.len() != 2 {
+            let err = format!("{} expects 2 arguments but got {}", REMOVE, args.len());
+            return Err(self.error_at(loc, &err));
+        }
+
+        let mut steps: Vec<Step> = Vec::new();
+        let (var, var_loc) = self.resolve_place(&args[0], &mut steps)?;
+
+        let key_type = match self.place_mut(&var, &var_loc, &steps)?.get_type() {
+            Type::Map(k, _) => *k,
+            t => {
+                let err = format!("argument 1 of {} expects a map but found {}", REMOVE, t);
+                return Err(self.error_at(&args[0].loc, &err));
+            }
+        };
+
+        let key = self.eval_key(&key_type, &args[1])?;
+
+        let removed = match self.place_mut(&var, &var_loc, &steps)? {
+            Value::Map(_, _, entries) => entries.remove(&key),
+            _                         => None,
+        };
+
+        match removed {
+            Some(v) => return Ok(Some(v)),
+            None    => return Err(self.missing_key(&key, &args[1].loc)),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// Array and map literals, indexing and slicing. Strings count in characters and arrays in elements, maps are indexed by key.
+use std::io;
+use crate::ast::Expr;
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::{ Entries, Value };
+
+impl<'a> Evaluator<'a> {
+
+    // [a, b, c], the elements take the hinted element type or the type of the first one.
+    pub fn eval_array(&mut self, elements: &[Expr], hint: Option<&Type>, loc: &Location) -> Result<Value, io::Error> {
+        let mut element_type: Option<Type> = match hint {
+            Some(Type::Array(t)) => Some((**t).clone()),
+            _                    => None,
+        };
+
+        let mut items: Vec<Value> = Vec::new();
+
+        for element in elements {
+            let v = self.eval_with(element, element_type.as_ref())?;
+            let t = element_type.get_or_insert_with(|| v.get_type()).clone();
+            items.push(self.coerce(v, &t, &element.loc)?);
+        }
+
+        match element_type {
+            Some(t) => return Ok(Value::Array(t, items)),
+            None    => return Err(self.error_at(loc, "the type of an empty array is not known here, define it with -> [T] first")),
+        }
+    }
+
+    // [v; n], the type checker made sure n is a constant between 0 and MAX_REPEAT, nested ones included.
+    // A variable can still hold a big array, running out of memory on any copy is an error at n rather than an abort.
+    pub fn eval_repeat(&mut self, value: &Expr, count: &Expr, hint: Option<&Type>) -> Result<Value, io::Error> {
+        let element_type = match hint {
+            Some(Type::Array(t)) => Some((**t).clone()),
+            _                    => None,
+        };
+
+        let v = self.eval_with(value, element_type.as_ref())?;
+        let t = element_type.unwrap_or_else(|| v.get_type());
+        let v = self.coerce(v, &t, &value.loc)?;
+
+        let n = self.eval(count)?;
+
+        let size = match n.as_i128().and_then(|n| usize::try_from(n).ok()) {
+            Some(size) => size,
+            None       => return Err(self.error_at(&count.loc, &format!("an array can not hold {} elements", n))),
+        };
+
+        let mut items: Vec<Value> = Vec::new();
+
+        if items.try_reserve_exact(size).is_err() {
+            return Err(self.error_at(&count.loc, &format!("there is not enough memory for an array of {} elements", n)));
+        }
+
+        for _ in 0..size {
+            match try_clone(&v) {
+                Some(copy) => items.push(copy),
+                None       => return Err(self.error_at(&count.loc, &format!("there is not enough memory for {} copies of the element", n))),
+            }
+        }
+
+        return Ok(Value::Array(t, items));
+    }
+
+    // {k: v, ...}, the entries take the hinted map type or the types of the first one.
+    pub fn eval_map(&mut self, entries: &[(Expr, Expr)], hint: Option<&Type>, loc: &Location) -> Result<Value, io::Error> {
+        let mut types: Option<(Type, Type)> = match hint {
+            Some(Type::Map(k, v)) => Some(((**k).clone(), (**v).clone())),
+            _                     => None,
+        };
+
+        let mut pairs = Entries::new();
+
+        for (key, value) in entries {
+            let k = self.eval_with(key, types.as_ref().map(|(k, _)| k))?;
+            let v = self.eval_with(value, types.as_ref().map(|(_, v)| v))?;
+
+            if types.is_none() && !k.get_type().is_key() {
+                let err = format!("a map key can be an integer, a string or a bool, not {}", k.get_type());
+                return Err(self.error_at(&key.loc, &err));
+            }
+
+            let (kt, vt) = types.get_or_insert_with(|| (k.get_type(), v.get_type())).clone();
+            let k = self.coerce(k, &kt, &key.loc)?;
+            let v = self.coerce(v, &vt, &value.loc)?;
+
+            if pairs.get(&k).is_some() {
+                return Err(self.error_at(&key.loc, &format!("key {} is in the map twice", k.quoted())));
+            }
+
+            pairs.insert(k, v);
+        }
+
+        match types {
+            Some((k, v)) => return Ok(Value::Map(k, v, pairs)),
+            None         => return Err(self.error_at(loc, "the type of an empty map is not known here, define it with -> map[K, V] first")),
+        }
+    }
+
+    // A key takes the key type of its map.
+    pub fn eval_key(&mut self, key_type: &Type, expr: &Expr) -> Result<Value, io::Error> {
+        let k = self.eval_with(expr, Some(key_type))?;
+        return self.coerce(k, key_type, &expr.loc);
+    }
+
+    // xs[i] or m[k] on a value that is not in a variable, see read for the ones that are.
+    pub fn index_value(&mut self, v: Value, index: &Expr, loc: &Location) -> Result<Value, io::Error> {
+        if let Value::Map(kt, _, entries) = &v {
+            let k = self.eval_key(kt, index)?;
+
+            match entries.get(&k) {
+                Some(found) => return Ok(found.clone()),
+                None        => return Err(self.missing_key(&k, loc)),
+            }
+        }
+
+        let i = self.eval_position(index)?;
+        return self.index(&v, i, loc);
+    }
+
+    pub fn missing_key(&self, key: &Value, loc: &Location) -> io::Error {
+        return self.error_at(loc, &format!("key {} is not in the map", key.quoted()));
+    }
+
+    pub fn eval_position(&mut self, expr: &Expr) -> Result<i128, io::Error> {
+        let v = self.eval(expr)?;
+
+        match v.as_i128() {
+            Some(i) => return Ok(i),
+            None => {
+                let err = format!("expected an integer position but found {}", v.get_type());
+                return Err(self.error_at(&expr.loc, &err));
+            }
+        }
+    }
+
+    // How many positions a value has and what they are called, None when it can not be indexed.
+    pub fn positions(value: &Value) -> Option<(usize, &'static str)> {
+        return match value {
+            Value::Str(s)          => Some((s.chars().count(), "characters")),
+            Value::Array(_, items) => Some((items.len(), "elements")),
+            _                      => None,
+        };
+    }
+
+    pub fn index(&self, value: &Value, i: i128, loc: &Location) -> Result<Value, io::Error> {
+        let (len, unit) = match Self::positions(value) {
+            Some(p) => p,
+            None    => return Err(self.error_at(loc, &format!("can not index {}", value.get_type()))),
+        };
+
+        let i = match usize::try_from(i) {
+            Ok(i) if i < len => i,
+            _ => {
+                let err = format!("index {} is out of bounds, the {} has {} {}", i, value.get_type(), len, unit);
+                return Err(self.error_at(loc, &err));
+            }
+        };
+
+        match value {
+            Value::Str(s)          => return Ok(Value::Str(s.chars().skip(i).take(1).collect())),
+            Value::Array(_, items) => return Ok(items[i].clone()),
+            _                      => unreachable!(),
+        }
+    }
+
+    pub fn slice(&self, value: &Value, start: Option<i128>, end: Option<i128>, inclusive: bool, loc: &Location) -> Result<Value, io::Error> {
+        let (len, unit) = match Self::positions(value) {
+            Some(p) => p,
+            None    => return Err(self.error_at(loc, &format!("can not slice {}", value.get_type()))),
+        };
+
+        let from = start.unwrap_or(0);
+        let to   = match end {
+            Some(e) if inclusive => e.saturating_add(1),
+            Some(e)              => e,
+            None                 => len as i128,
+        };
+
+        let range = match (usize::try_from(from), usize::try_from(to)) {
+            (Ok(a), Ok(b)) if a <= b && b <= len => a..b,
+            _ => {
+                let dots = if inclusive { "..=" } else { ".." };
+                let show = |b: Option<i128>| b.map(|b| b.to_string()).unwrap_or_default();
+                let err = format!("slice {}{}{} is out of bounds, the {} has {} {}", show(start), dots, show(end), value.get_type(), len, unit);
+                return Err(self.error_at(loc, &err));
+            }
+        };
+
+        match value {
+            Value::Str(s)          => return Ok(Value::Str(s.chars().skip(range.start).take(range.len()).collect())),
+            Value::Array(t, items) => return Ok(Value::Array(t.clone(), items[range].to_vec())),
+            _                      => unreachable!(),
+        }
+    }
+}
+
+// A copy that reports running out of memory instead of aborting, arrays and strings are where the memory goes.
+fn try_clone(v: &Value) -> Option<Value> {
+    match v {
+        Value::Array(t, items) => {
+            let mut copy: Vec<Value> = Vec::new();
+            copy.try_reserve_exact(items.len()).ok()?;
+
+            for item in items {
+                copy.push(try_clone(item)?);
+            }
+
+            return Some(Value::Array(t.clone(), copy));
+        },
+        Value::Str(s) => {
+            let mut copy = String::new();
+            copy.try_reserve_exact(s.len()).ok()?;
+            copy.push_str(s);
+            return Some(Value::Str(copy));
+        },
+        _ => return Some(v.clone()),
+    }
+}
@@ -1,180 +1,102 @@
 // STACK.
 use std::collections::HashMap;
-use crate::enums::BOOL_TRUE;
-
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+pub struct Variable {
+    pub value:         Value,
+    pub declared_type: Type,
+    pub loc:           Location, // Where it was defined.
+}
 
+// Where a variable that went out of scope was defined, and where its block ended.
+pub struct Expired {
+    pub loc: Location,
+    pub end: Location,
+}
 
 pub struct Stack {
-    
-    pub bool_map:     HashMap<String, bool>,
-    pub str_map:      HashMap<String, String>,
-    pub int_map_8:    HashMap<String, i8>, // 8 bit..
-    pub int_map_16:   HashMap<String, i16>, // 16 bit..
-    pub int_map:      HashMap<String, i32>, // 32 bit.     
-    pub int_map_64:   HashMap<String, i64>, // 64 bit..
-    pub int_map_128:  HashMap<String, i128>, // 128 bit..
-     
+    scopes:     Vec<HashMap<String, Variable>>, // Innermost scope last..
+    expired:    HashMap<String, Expired>,       // Names dropped at a closing }..
+    frame_base: usize,                          // First scope of the running process..
 }
 
 
-#[allow(dead_code)]
 impl Stack {
     pub fn new() -> Self {
         Self {
-            bool_map:           HashMap::new(),
-            str_map:            HashMap::new(), 
-            
-            int_map_8:          HashMap::new(),
-            int_map_16:         HashMap::new(),
-            int_map:            HashMap::new(),
-            int_map_64:         HashMap::new(),
-            int_map_128:        HashMap::new(),
-            
+            scopes: vec![HashMap::new()], // Global scope.
+            expired: HashMap::new(),
+            frame_base: 0,
         }
     }
-     
-    pub fn print_variable(&mut self, key: &String) -> bool {
 
-            if let Some(v) = self.get_from_int_map(key) {
-                print!("{}", v);
-                return true;
-            }
+    // A process call only sees its own scopes and the globals.
+    // Returns the base of the caller, hand it back to pop_frame.
+    pub fn push_frame(&mut self) -> usize {
+        let saved = self.frame_base;
+        self.frame_base = self.scopes.len();
+        self.scopes.push(HashMap::new());
+        return saved;
+    }
 
-            if let Some(v) = self.get_from_str_map(key) {
-                print!("{}", v);
-                return true;
-            }
-            
+    pub fn pop_frame(&mut self, saved: usize) {
+        self.scopes.truncate(self.frame_base);
+        self.frame_base = saved;
+    }
 
-            if let Some(v) = self.get_from_int_map_64(key) { 
-                print!("{}", v);
-                return true;
-            }
-            
-            if let Some(v) = self.get_from_bool_map(key) {
-                if *v {
-                    print!("{}", BOOL_TRUE);
-                    return true;           
-                }
-                
-                print!("False");
-                return true;
-            }
+    // The scopes visible from here: the running frame, innermost first, then the globals.
+    fn visible(&self) -> impl Iterator<Item = usize> {
+        let globals = if self.frame_base > 0 { Some(0) } else { None };
+        return (self.frame_base..self.scopes.len()).rev().chain(globals);
+    }
 
-            return false;
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
     }
 
-    pub fn get_int(&mut self, key: &String) -> Option<f64>{
-        
-        if let Some(v) = self.get_from_int_map(key) {
-            return Some(*v as f64);
+    // Drops every variable defined in the innermost scope, end is the location of its }.
+    pub fn pop_scope(&mut self, end: Location) {
+        if self.scopes.len() <= self.frame_base + 1 {
+            return; // Never drop the globals or the frame itself.
         }
 
+        if let Some(scope) = self.scopes.pop() {
+            for (name, var) in scope {
+                self.expired.insert(name, Expired { loc: var.loc, end });
+            }
+        }
+    }
+
+    pub fn define(&mut self, key: &str, declared_type: Type, value: Value, loc: Location) {
+        self.expired.remove(key);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(key.to_string(), Variable { value, declared_type, loc });
+        }
+    }
 
-        if let Some(v) = self.get_from_int_map_64(key) { 
-            return Some(*v as f64);
+    pub fn get(&self, key: &str) -> Option<&Variable> {
+        for i in self.visible() {
+            if let Some(var) = self.scopes[i].get(key) {
+                return Some(var);
+            }
         }
 
         return None;
     }
-    
-    pub fn defined(&mut self, key: &String) -> bool {
-        return self.int_map.contains_key(key) 
-            || self.str_map.contains_key(key) 
-            || self.int_map_64.contains_key(key)
-            || self.bool_map.contains_key(key);
-    }
-    
-    pub fn get_from_bool_map(&mut self, key: &String) -> Option<&bool> {
-
-        if self.bool_map.contains_key(key) {
-            return Some(&self.bool_map[key]);
-        }  
-
-        return None; 
-    } 
-    
-    pub fn get_from_int_map_64(&mut self, key: &String) -> Option<&i64> {
-
-        if self.int_map_64.contains_key(key) {
-            return Some(&self.int_map_64[key]);
-        }  
-
-        return None; 
-    }  
-
-    pub fn get_from_int_map(&mut self, key: &String) -> Option<&i32> {
-        if self.int_map.contains_key(key) {
-            return Some(&self.int_map[key]);
-        }  
-
-        return None; 
-    }  
-    
-    pub fn get_from_str_map(&mut self, key: &String) -> Option<&String> {
-        
-        if self.str_map.contains_key(key) {
-            return Some(&self.str_map[key]);
-        }
 
-        return None; 
-    }  
-/* 
-    pub fn get_from_int_map_8(&mut self, key: &String) -> Option<&i8> {
-        if self.int_map_8.contains_key(&key) {
-            return Some(&self.int_map_8[&key]);
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Variable> {
+        let found = self.visible().find(|i| self.scopes[*i].contains_key(key));
+
+        match found {
+            Some(i) => return self.scopes[i].get_mut(key),
+            None    => return None,
         }
+    }
 
-        return None; 
-    }  
-     
-    pub fn get_from_int_map_16(&mut self, key: &String) -> Option<&i16> {
-        if self.int_map_16.contains_key(&key) {
-            return Some(&self.int_map_16[&key]);
-        } 
-    
-        return None;
-    }  
-     
-    
-    
-    pub fn get_from_int_map_128(&mut self, key: &String) -> Option<&i128> {
-        if self.int_map_128.contains_key(&key) {
-            return Some(&self.int_map_128[&key]);
-        }  
-    
-        return None; 
-    }  
-*/    
-    pub fn push_str_map(&mut self, key: &String, value: String) {
-         self.str_map.insert(key.clone(), value);
-    } 
-    
-    pub fn push_bool_map(&mut self, key: &String, value: bool) {
-         self.bool_map.insert(key.clone(), value);
-    } 
-    
-    pub fn push_int_map(&mut self, key: &String, value: i32) {
-        self.int_map.insert(key.clone(), value);
-    }  
-    
-    pub fn push_int_map_64(&mut self, key: &String, value: i64) {
-        self.int_map_64.insert(key.clone(), value);
-    }  
-
-    /*     
-    pub fn set_int_map_8(&mut self, key: String, value: i8) {
-        self.int_map_8.insert(key, value);
-    }  
-     
-    pub fn set_int_map_16(&mut self, key: String, value: i16) {
-        self.int_map_16.insert(key, value);
-    }  
-     
-    
-         
-    pub fn set_int_map_128(&mut self, key: String, value: i128) {
-        self.int_map_128.insert(key, value);
+    pub fn get_expired(&self, key: &str) -> Option<&Expired> {
+        return self.expired.get(key);
     }
-*/
 }
@@ -0,0 +1,195 @@
+// Structs, named records of typed fields. Their declarations, and those of enums, are checked before anything runs.
+use std::io;
+use crate::ast::{ Expr, Proc, StructDecl, EnumDecl };
+use crate::enums::BUILTINS;
+use crate::evaluator::Evaluator;
+use crate::token::Location;
+use crate::types::Type;
+use crate::value::Value;
+
+impl<'a> Evaluator<'a> {
+
+    // Structs and enums share their names, a type is declared once.
+    pub fn check_new_type(&self, name: &str, loc: &Location) -> Result<(), io::Error> {
+        let other = match (self.structs.get(name), self.enums.get(name)) {
+            (Some(decl), _) => decl.loc,
+            (_, Some(decl)) => decl.loc,
+            _               => return Ok(()),
+        };
+
+        let err = format!("type {} is already defined at {}:{}:{}", name, self.file_path, other.row, other.col);
+        return Err(self.error_at(loc, &err));
+    }
+
+    // Every type a struct, an enum or a process names must be declared, and no struct can hold itself.
+    pub fn check_declarations(&self) -> Result<(), io::Error> {
+        // In the order they were written, so the first mistake in the file is the one reported.
+        let mut decls: Vec<&'a StructDecl> = self.structs.values().copied().collect();
+        decls.sort_by_key(|decl| (decl.loc.row, decl.loc.col));
+
+        for decl in &decls {
+            for field in &decl.fields {
+                self.check_type(&field.declared_type, &field.loc)?;
+            }
+        }
+
+        for decl in &decls {
+            if self.holds(decl, &decl.name, &mut Vec::new()) {
+                let err = format!("struct {} holds itself and would never end, hold it in an array or a map instead", decl.name);
+                return Err(self.error_at(&decl.loc, &err));
+            }
+        }
+
+        let mut enums: Vec<&'a EnumDecl> = self.enums.values().copied().collect();
+        enums.sort_by_key(|decl| (decl.loc.row, decl.loc.col));
+
+        for decl in enums {
+            for variant in &decl.variants {
+                for t in &variant.fields {
+                    self.check_type(t, &variant.loc)?;
+                }
+
+                // A variant is built like a call, a process of the same name would hide it.
+                if BUILTINS.contains(&variant.name.as_str()) || self.procs.contains_key(&variant.name) {
+                    let err = format!("variant {} of {} has the name of a process, rename one of them", variant.name, decl.name);
+                    return Err(self.error_at(&variant.loc, &err));
+                }
+            }
+        }
+
+        let mut procs: Vec<&'a Proc> = self.procs.values().copied().collect();
+        procs.sort_by_key(|proc| (proc.loc.row, proc.loc.col));
+
+        for proc in procs {
+            for param in &proc.params {
+                self.check_type(&param.declared_type, &param.loc)?;
+            }
+
+            if let Some(t) = &proc.return_type {
+                self.check_type(t, &proc.loc)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    // Whether decl has a field of struct name, directly or through the fields of its fields.
+    // Arrays and maps start out empty, holding a struct through them is fine.
+    fn holds(&self, decl: &StructDecl, name: &str, seen: &mut Vec<String>) -> bool {
+        for field in &decl.fields {
+            if let Type::Named(inner) = &field.declared_type {
+                if inner == name {
+                    return true;
+                }
+
+                if seen.contains(inner) {
+                    continue;
+                }
+
+                seen.push(inner.clone());
+
+                if let Some(inner) = self.structs.get(inner) {
+                    if self.holds(inner, name, seen) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        return false;
+    }
+
+    pub fn check_type(&self, t: &Type, loc: &Location) -> Result<(), io::Error> {
+        match t {
+            Type::Named(name) if !self.structs.contains_key(name) && !self.enums.contains_key(name) => {
+                let err = format!("unknown type {}, declare it with struct {} {{ ... }} or enum {} {{ ... }}", name, name, name);
+                return Err(self.error_at(loc, &err));
+            },
+            Type::Array(t)  => return self.check_type(t, loc),
+            Type::Map(_, v) => return self.check_type(v, loc),
+            _               => return Ok(()),
+        }
+    }
+
+    // A struct defined without a value has every field at its own default, an enum has no default to pick.
+    pub fn default_value(&self, t: &Type, loc: &Location) -> Result<Value, io::Error> {
+        if let Type::Named(name) = t {
+            let decl = match self.structs.get(name) {
+                Some(decl) => decl,
+                None => {
+                    let err = format!("{} is an enum and has no default value, define it with = and one of its variants", name);
+                    return Err(self.error_at(loc, &err));
+                }
+            };
+
+            let mut fields: Vec<(String, Value)> = Vec::new();
+
+            for f in &decl.fields {
+                fields.push((f.name.clone(), self.default_value(&f.declared_type, loc)?));
+            }
+
+            return Ok(Value::Struct(name.clone(), fields));
+        }
+
+        return Ok(Value::default_for(t));
+    }
+
+    // Name { a: expr, ... }, every field is given once, in any order.
+    pub fn eval_struct(&mut self, name: &str, fields: &[(String, Expr)], loc: &Location) -> Result<Value, io::Error> {
+        let decl: &'a StructDecl = match self.structs.get(name) {
+            Some(decl) => decl,
+            None => {
+                let err = format!("unknown struct {}, declare it with struct {} {{ ... }}", name, name);
+                return Err(self.error_at(loc, &err));
+            }
+        };
+
+        let mut values: Vec<Option<Value>> = vec![None; decl.fields.len()];
+
+        for (field, expr) in fields {
+            let i = match decl.fields.iter().position(|f| f.name == *field) {
+                Some(i) => i,
+                None    => return Err(self.error_at(&expr.loc, &format!("{} has no field {}", name, field))),
+            };
+
+            if values[i].is_some() {
+                return Err(self.error_at(&expr.loc, &format!("field {} of {} is given twice", field, name)));
+            }
+
+            let t     = &decl.fields[i].declared_type;
+            let v     = self.eval_with(expr, Some(t))?;
+            let found = v.get_type();
+
+            match self.coerce(v, t, &expr.loc) {
+                Ok(v)  => values[i] = Some(v),
+                Err(_) => {
+                    let err = format!("field {} of {} holds {} but found {}", field, name, t, found);
+                    return Err(self.error_at(&expr.loc, &err));
+                }
+            }
+        }
+
+        let mut result: Vec<(String, Value)> = Vec::new();
+
+        for (f, v) in decl.fields.iter().zip(values) {
+            match v {
+                Some(v) => result.push((f.name.clone(), v)),
+                None    => return Err(self.error_at(loc, &format!("{} needs a value for field {}", name, f.name))),
+            }
+        }
+
+        return Ok(Value::Struct(name.to_string(), result));
+    }
+
+    pub fn field(&self, value: Value, name: &str, loc: &Location) -> Result<Value, io::Error> {
+        let t = value.get_type();
+
+        if let Value::Struct(_, fields) = value {
+            if let Some((_, v)) = fields.into_iter().find(|(f, _)| f == name) {
+                return Ok(v);
+            }
+        }
+
+        return Err(self.error_at(loc, &format!("{} has no field {}", t, name)));
+    }
+}
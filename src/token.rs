@@ -1,7 +1,7 @@
 // Token.
 use crate::enums::TokenT;
 
-#[derive(Clone)]
+#[derive(Debug, Copy, Clone)]
 pub struct Location {
     pub row: usize,
     pub col: usize,
@@ -38,7 +38,7 @@ impl Token {
     
     pub fn _new(value: String, type_: TokenT) -> Self {
         Token {
-            value: value,
+            value,
             token_type: type_,
             size: 0,
             loc: Location::empty(),
@@ -0,0 +1,1196 @@
+// Type checking, a pass over the whole program before anything runs.
+// It applies the rules the evaluator applies to values to their types, and reports every mismatch instead of the first.
+use std::io;
+use std::collections::HashMap;
+use crate::ast::*;
+use crate::enums::*;
+use crate::evaluator::Evaluator;
+use crate::stack::Expired;
+use crate::token::Location;
+use crate::types::Type;
+use crate::util::make_error;
+use crate::value::Value;
+
+// What a call gives back.
+enum Ret {
+    Value(Type),
+    Nothing, // A process without -> T, write or push.
+    Unknown, // An error was reported already.
+}
+
+// A name in scope.
+struct Name {
+    t:        Option<Type>,  // None when the expression it came from did not check, so it does not report again...
+    loc:      Location,
+    constant: bool,
+    value:    Option<Value>, // What a const folded to, when it is known before the program runs...
+}
+
+type Scope = HashMap<String, Name>;
+
+pub struct TypeChecker<'e, 'a> {
+    ev:      &'e Evaluator<'a>,          // The declarations and the rules...
+    scopes:  Vec<Scope>,                 // Innermost scope last...
+    globals: Scope,                      // What process bodies see besides their own names...
+    expired: HashMap<String, Expired>,   // Names dropped at a closing }...
+    current: Option<&'a Proc>,           // The process being checked...
+    errors:  Vec<(Location, io::Error)>, // Everything found so far...
+    warnings: Vec<(Location, String)>,   // Shadowed names, with --warn-shadow...
+    copies:  i128,                       // How many times the [v; n] around the expression being checked copy it...
+}
+
+impl<'e, 'a> TypeChecker<'e, 'a> {
+
+    pub fn new(ev: &'e Evaluator<'a>) -> Self {
+        Self {
+            ev,
+            scopes: vec![HashMap::new()], // Global scope.
+            globals: HashMap::new(),
+            expired: HashMap::new(),
+            current: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            copies: 1,
+        }
+    }
+
+    // The top level is checked first, the bodies of processes then see every global.
+    pub fn check(mut self, program: &'a Program) -> Result<(), io::Error> {
+        for stmt in &program.stmts {
+            self.stmt(stmt);
+        }
+
+        self.globals = self.scopes.remove(0);
+
+        for stmt in &program.stmts {
+            if let StmtKind::Process(proc) = &stmt.kind {
+                self.process(proc);
+            }
+        }
+
+        // Warnings do not stop the program, they are written before it runs.
+        self.warnings.sort_by_key(|(loc, _)| (loc.row, loc.col));
+
+        for (_, warning) in &self.warnings {
+            println!("{}", warning);
+        }
+
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        // In the order of the file, one per line.
+        self.errors.sort_by_key(|(loc, _)| (loc.row, loc.col));
+        let lines: Vec<String> = self.errors.iter().map(|(_, e)| e.to_string()).collect();
+        return Err(make_error(&lines.join("\n")));
+    }
+
+    fn report(&mut self, e: io::Error, loc: &Location) {
+        self.errors.push((*loc, e));
+    }
+
+    fn error(&mut self, loc: &Location, text: &str) {
+        let e = self.ev.error_at(loc, text);
+        self.report(e, loc);
+    }
+
+    fn process(&mut self, proc: &'a Proc) {
+        // Like a call, the parameters are a scope of their own around the body.
+        self.current = Some(proc);
+        self.scopes  = vec![HashMap::new()];
+
+        for param in &proc.params {
+            self.define(&param.name, Some(param.declared_type.clone()), param.loc);
+        }
+
+        self.block(&proc.body);
+        self.current = None;
+
+        if let Some(t) = &proc.return_type {
+            if !proc.body.always_returns() {
+                let err = format!("{} must return {} {} but can reach the end of its body without a return", proc.name, t.article(), t);
+                self.error(&proc.loc, &err);
+            }
+        }
+    }
+
+    fn define(&mut self, name: &str, t: Option<Type>, loc: Location) {
+        self.insert(name, Name { t, loc, constant: false, value: None });
+    }
+
+    // A name is defined once in a scope, an inner scope can hide it.
+    fn insert(&mut self, name: &str, entry: Name) {
+        self.expired.remove(name);
+
+        let previous = self.scopes.last().and_then(|scope| scope.get(name)).map(|p| p.loc);
+        let outer    = self.lookup(name).map(|o| o.loc);
+
+        if let Some(previous) = previous {
+            let err = format!("{} is already defined in this scope at {}, rename one of them", name, self.at(previous));
+            self.error(&entry.loc, &err);
+        } else if let (Some(outer), true) = (outer, self.ev.warn_shadow) {
+            let warning = format!("warning, {} hides the {} defined at {}", name, name, self.at(outer));
+            self.warnings.push((entry.loc, format!("{} {}", self.at(entry.loc), warning)));
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), entry);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Name> {
+        return self.scopes.iter().rev().chain([&self.globals]).find_map(|scope| scope.get(name));
+    }
+
+    fn undefined(&mut self, name: &str, loc: &Location) {
+        let e = self.ev.not_defined(name, self.expired.get(name), loc);
+        self.report(e, loc);
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self, end: Location) {
+        if let Some(scope) = self.scopes.pop() {
+            for (name, entry) in scope {
+                self.expired.insert(name, Expired { loc: entry.loc, end });
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.push_scope();
+
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+
+        self.pop_scope(block.end);
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Define { name, declared_type, init, constant } => self.define_stmt(name, declared_type, init, *constant, &stmt.loc),
+            StmtKind::Assign { name, value } => {
+                let t = match self.lookup(name) {
+                    Some(entry) if entry.constant => {
+                        let err = self.changed_const(name, entry.loc);
+                        self.error(&stmt.loc, &err);
+                        self.expr(value, None);
+                        return;
+                    },
+                    Some(entry) => entry.t.clone(),
+                    None => {
+                        self.undefined(name, &stmt.loc);
+                        self.expr(value, None);
+                        return;
+                    }
+                };
+
+                let found = self.expr(value, t.as_ref());
+
+                if let (Some(t), Some(found)) = (t, found) {
+                    if !Self::assignable(&found, &t) {
+                        let err = format!("can not assign {} to {}, it was defined as {}", found, name, t);
+                        self.error(&value.loc, &err);
+                    }
+                }
+            },
+            StmtKind::AssignTo { target, value } => self.assign_to(target, value),
+            StmtKind::If { cond, then_block, else_block } => {
+                self.cond(cond);
+                self.block(then_block);
+
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            },
+            StmtKind::While { cond, body } => {
+                self.cond(cond);
+                self.block(body);
+            },
+            StmtKind::For { var, range, body } => {
+                self.expect(&range.start, &Type::I32);
+                self.expect(&range.end, &Type::I32);
+
+                if let Some(step) = &range.step {
+                    self.expect(step, &Type::I32);
+                }
+
+                self.push_scope();
+                self.define(var, Some(Type::I32), stmt.loc);
+                self.block(body);
+                self.pop_scope(body.end);
+            },
+            StmtKind::ForEach { var, iterable, body } => {
+                let element = match self.expr(iterable, None) {
+                    Some(Type::Array(t))  => Some(*t),
+                    Some(Type::Map(k, _)) => Some(*k),
+                    Some(Type::Str)       => Some(Type::Str),
+                    Some(t) => {
+                        let err = format!("can not iterate over {}, expected an array, a map, a string or a range", t);
+                        self.error(&iterable.loc, &err);
+                        None
+                    },
+                    None => None,
+                };
+
+                self.push_scope();
+                self.define(var, element, stmt.loc);
+                self.block(body);
+                self.pop_scope(body.end);
+            },
+            StmtKind::Block(block) => self.block(block),
+            StmtKind::Return(value) => self.ret(value, &stmt.loc),
+            StmtKind::Expr(expr)    => self.expr_stmt(expr),
+            StmtKind::Match(m)      => self.match_stmt(m, &stmt.loc),
+            StmtKind::Break | StmtKind::Continue => {},
+            StmtKind::Process(_) | StmtKind::Struct(_) | StmtKind::Enum(_) => {}, // Bodies are checked after the top level.
+        }
+    }
+
+    fn define_stmt(&mut self, name: &str, declared_type: &Option<Type>, init: &Option<Expr>, constant: bool, loc: &Location) {
+        let errors = self.errors.len();
+
+        let t = match declared_type {
+            // define x = expr, the parser makes sure there is a value to take the type from.
+            None => init.as_ref().and_then(|expr| self.expr(expr, None)),
+            Some(t) => {
+                if let Err(e) = self.ev.check_type(t, loc) {
+                    self.report(e, loc);
+                    self.define(name, None, *loc);
+                    return;
+                }
+
+                match init {
+                    Some(expr) => self.expect(expr, t),
+                    None => {
+                        if let Err(e) = self.ev.default_value(t, loc) {
+                            self.report(e, loc);
+                        }
+                    },
+                }
+
+                Some(t.clone())
+            },
+        };
+
+        // A const that checked is folded now, so it can size an array.
+        let mut value: Option<Value> = None;
+
+        if let (true, Some(t), Some(expr)) = (constant, &t, init) {
+            if self.errors.len() == errors {
+                value = self.fold(expr, Some(t)).and_then(|v| match v {
+                    Some(v) => self.ev.coerce(v, t, &expr.loc).map(Some),
+                    None    => Ok(None),
+                }).unwrap_or_else(|e| {
+                    self.report(e, &expr.loc);
+                    None
+                });
+            }
+        }
+
+        self.insert(name, Name { t, loc: *loc, constant, value });
+    }
+
+    fn changed_const(&self, name: &str, loc: Location) -> String {
+        return format!("{} is a const and can not change, it was defined at {}", name, self.at(loc));
+    }
+
+    fn at(&self, loc: Location) -> String {
+        return format!("{}:{}:{}", self.ev.file_path, loc.row, loc.col);
+    }
+
+    // The value of an expression made of literals and consts, None when it takes the program running to know it.
+    // It is computed by the rules the evaluator runs it with, so an overflow here is an overflow there.
+    fn fold(&self, expr: &Expr, hint: Option<&Type>) -> Result<Option<Value>, io::Error> {
+        let hint = hint.filter(|t| t.is_number());
+        let loc  = &expr.loc;
+
+        match &expr.kind {
+            ExprKind::Int(v)   => return self.ev.int_literal(*v, false, hint, loc).map(Some),
+            ExprKind::Float(v) => {
+                match hint {
+                    Some(Type::F32) => return Ok(Some(Value::F32(*v as f32))),
+                    _               => return Ok(Some(Value::F64(*v))),
+                }
+            },
+            ExprKind::Str(s)    => return Ok(Some(Value::Str(s.clone()))),
+            ExprKind::Bool(b)   => return Ok(Some(Value::Bool(*b))),
+            ExprKind::Var(name) => return Ok(self.lookup(name).and_then(|entry| entry.value.clone())),
+            ExprKind::Cast { operand, target } => {
+                match self.fold(operand, None)? {
+                    Some(v) => return self.ev.cast(v, target, loc).map(Some),
+                    None    => return Ok(None),
+                }
+            },
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                if let ExprKind::Int(v) = operand.kind {
+                    return self.ev.int_literal(v, true, hint, loc).map(Some);
+                }
+
+                match self.fold(operand, hint)? {
+                    Some(v) => return self.ev.negate(&v, loc).map(Some),
+                    None    => return Ok(None),
+                }
+            },
+            ExprKind::Unary { op: UnaryOp::Not, operand } => {
+                match self.fold(operand, None)? {
+                    Some(Value::Bool(b)) => return Ok(Some(Value::Bool(!b))),
+                    _                    => return Ok(None),
+                }
+            },
+            ExprKind::Binary { op, lhs, rhs } => {
+                // Comparisons and logic do not pass the hint on, the rhs of a comparison takes the type of the lhs.
+                let hint = if op.is_comparison() || op.is_logical() { None } else { hint };
+
+                let l = match self.fold(lhs, hint)? {
+                    Some(l) => l,
+                    None    => return Ok(None),
+                };
+
+                let t = l.get_type();
+
+                let r = match self.fold(rhs, hint.or(Some(&t)))? {
+                    Some(r) => r,
+                    None    => return Ok(None),
+                };
+
+                match (op, l, r) {
+                    (BinOp::And, Value::Bool(a), Value::Bool(b)) => return Ok(Some(Value::Bool(a && b))),
+                    (BinOp::Or, Value::Bool(a), Value::Bool(b))  => return Ok(Some(Value::Bool(a || b))),
+                    (BinOp::And | BinOp::Or, ..)                 => return Ok(None),
+                    (op, l, r) if op.is_comparison()             => return self.ev.compare(*op, &l, &r, loc).map(|b| Some(Value::Bool(b))),
+                    (op, l, r)                                   => return self.ev.arith(*op, &l, &r, loc).map(Some),
+                }
+            },
+            _ => return Ok(None),
+        }
+    }
+
+    // An expression used for its side effects, a call there does not need to give a value.
+    fn expr_stmt(&mut self, expr: &Expr) {
+        if let ExprKind::Call { name, args } = &expr.kind {
+            self.call(name, args, &expr.loc);
+            return;
+        }
+
+        self.expr(expr, None);
+    }
+
+    fn ret(&mut self, value: &Option<Expr>, loc: &Location) {
+        let proc = match self.current {
+            Some(proc) => proc,
+            None       => return, // The parser keeps return inside processes.
+        };
+
+        match (value, &proc.return_type) {
+            (Some(expr), Some(t)) => {
+                if let Some(found) = self.expr(expr, Some(t)) {
+                    if !Self::assignable(&found, t) {
+                        let err = Self::mismatch(&found, t);
+                        self.error(loc, &err);
+                    }
+                }
+            },
+            (Some(expr), None) => {
+                self.expr(expr, None);
+                let err = format!("{} does not return a value, remove the value or add -> T to its declaration", proc.name);
+                self.error(loc, &err);
+            },
+            (None, Some(t)) => self.error(loc, &format!("{} must return a {}", proc.name, t)),
+            (None, None)    => {},
+        }
+    }
+
+    // The value fits where it is headed, the way coerce would take it.
+    fn assignable(found: &Type, expected: &Type) -> bool {
+        if found == expected {
+            return true;
+        }
+
+        // Whether an int fits another int depends on its value, that is checked when it runs.
+        if expected.is_int() {
+            return found.is_int();
+        }
+
+        return expected.is_float() && found.is_number() && *found != Type::F64;
+    }
+
+    fn mismatch(found: &Type, expected: &Type) -> String {
+        if expected.is_int() && found.is_float() {
+            return format!("expected {} but found {}, a {} does not turn into an integer on its own, cast it with as", expected, found, found);
+        }
+
+        return format!("expected {} but found {}", expected, found);
+    }
+
+    fn expect(&mut self, expr: &Expr, expected: &Type) {
+        if let Some(found) = self.expr(expr, Some(expected)) {
+            if !Self::assignable(&found, expected) {
+                let err = Self::mismatch(&found, expected);
+                self.error(&expr.loc, &err);
+            }
+        }
+    }
+
+    // The type of an expression, None when it does not check. hint plays the part it plays in eval_with.
+    fn expr(&mut self, expr: &Expr, hint: Option<&Type>) -> Option<Type> {
+        let hint = hint.filter(|t| t.is_number() || matches!(t, Type::Array(_) | Type::Map(..)));
+
+        match &expr.kind {
+            ExprKind::Int(v)   => return self.int_literal(*v, false, hint, &expr.loc),
+            ExprKind::Float(_) => {
+                match hint {
+                    Some(Type::F32) => return Some(Type::F32),
+                    _               => return Some(Type::F64),
+                }
+            },
+            ExprKind::Str(_)  => return Some(Type::Str),
+            ExprKind::Bool(_) => return Some(Type::Bool),
+            ExprKind::Interp(pieces) => {
+                for piece in pieces {
+                    self.expr(piece, None);
+                }
+
+                return Some(Type::Str);
+            },
+            ExprKind::Array(elements) => return self.array(elements, hint, &expr.loc),
+            ExprKind::Repeat { value, count } => return self.repeat(value, count, hint),
+            ExprKind::Map(entries)    => return self.map(entries, hint, &expr.loc),
+            ExprKind::StructLit { name, fields } => return self.struct_lit(name, fields, &expr.loc),
+            ExprKind::Field { target, name } => {
+                let t = self.expr(target, None)?;
+                return self.field(&t, name, &expr.loc);
+            },
+            ExprKind::Var(name) => {
+                // A variant without a payload is a value on its own, unless a variable took its name.
+                if let Some(entry) = self.lookup(name) {
+                    return entry.t.clone();
+                }
+
+                if self.ev.variants.contains_key(name) {
+                    return self.construct(name, &[], &expr.loc);
+                }
+
+                self.undefined(name, &expr.loc);
+                return None;
+            },
+            ExprKind::Call { name, args } => {
+                match self.call(name, args, &expr.loc) {
+                    Ret::Value(t) => return Some(t),
+                    Ret::Unknown  => return None,
+                    Ret::Nothing  => {
+                        self.error(&expr.loc, &format!("{} does not return a value", name));
+                        return None;
+                    },
+                }
+            },
+            ExprKind::Match(m) => return self.match_expr(m, hint, &expr.loc),
+            ExprKind::Index { target, index } => {
+                let t = self.expr(target, None)?;
+                return self.index(&t, index, &expr.loc);
+            },
+            ExprKind::Slice { target, start, end, .. } => {
+                let t = self.expr(target, None);
+
+                for bound in [start, end].into_iter().flatten() {
+                    self.position(bound);
+                }
+
+                match t? {
+                    Type::Str          => return Some(Type::Str),
+                    t @ Type::Array(_) => return Some(t),
+                    t => {
+                        self.error(&expr.loc, &format!("can not slice {}", t));
+                        return None;
+                    }
+                }
+            },
+            ExprKind::Cast { operand, target } => {
+                let from = self.expr(operand, None)?;
+                return self.cast(&from, target, &expr.loc);
+            },
+            ExprKind::Unary { op: UnaryOp::Not, .. } => {
+                self.cond(expr);
+                return Some(Type::Bool);
+            },
+            ExprKind::Binary { op, .. } if op.is_comparison() || op.is_logical() => {
+                self.cond(expr);
+                return Some(Type::Bool);
+            },
+            ExprKind::Unary { op: UnaryOp::Neg, operand } => {
+                // -2147483648 is an int, 2147483648 alone is not.
+                if let ExprKind::Int(v) = operand.kind {
+                    return self.int_literal(v, true, hint, &expr.loc);
+                }
+
+                let t = self.expr(operand, hint)?;
+
+                if t.is_number() {
+                    return Some(t);
+                }
+
+                self.error(&expr.loc, &format!("unsupported operand type {}{}", UnaryOp::Neg, t));
+                return None;
+            },
+            ExprKind::Binary { op, lhs, rhs } => {
+                let l = self.expr(lhs, hint);
+                let r = self.expr(rhs, hint.or(l.as_ref()));
+                return self.arith(*op, &l?, &r?, &expr.loc);
+            },
+        }
+    }
+
+    fn int_literal(&mut self, v: u128, negative: bool, hint: Option<&Type>, loc: &Location) -> Option<Type> {
+        match self.ev.int_literal(v, negative, hint, loc) {
+            Ok(value) => return Some(value.get_type()),
+            Err(e) => {
+                self.report(e, loc);
+                return None;
+            }
+        }
+    }
+
+    // The rules of eval_bool, anything that decides a branch is a bool.
+    fn cond(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Bool(_) => {},
+            ExprKind::Unary { op: UnaryOp::Not, operand } => self.cond(operand),
+            ExprKind::Binary { op: BinOp::And | BinOp::Or, lhs, rhs } => {
+                self.cond(lhs);
+                self.cond(rhs);
+            },
+            ExprKind::Binary { op, lhs, rhs } if op.is_comparison() => {
+                let l = self.expr(lhs, None);
+                let r = self.expr(rhs, l.as_ref());
+
+                if let (Some(l), Some(r)) = (l, r) {
+                    self.compare(*op, &l, &r, &expr.loc);
+                }
+            },
+            ExprKind::Var(name) => {
+                if let Some(t) = self.expr(expr, None) {
+                    if t != Type::Bool {
+                        self.error(&expr.loc, &format!("{} is not a bool, it was defined as {}", name, t));
+                    }
+                }
+            },
+            ExprKind::Call { name, .. } => {
+                if let Some(t) = self.expr(expr, None) {
+                    if t != Type::Bool {
+                        self.error(&expr.loc, &format!("{} returns {}, expected a bool", name, t));
+                    }
+                }
+            },
+            _ => {
+                if let Some(t) = self.expr(expr, None) {
+                    if t != Type::Bool {
+                        self.error(&expr.loc, &format!("expected a bool but found {}", t));
+                    }
+                }
+            },
+        }
+    }
+
+    fn compare(&mut self, op: BinOp, l: &Type, r: &Type, loc: &Location) {
+        let equality = op == BinOp::Eq || op == BinOp::NotEq;
+
+        let kind = match (l, r) {
+            (Type::Str, Type::Str) => return,
+            (Type::Array(_), _) if l == r => "arrays",
+            (Type::Map(..), _) if l == r  => "maps",
+            (Type::Named(name), _) if l == r => if self.ev.enums.contains_key(name) { "enums" } else { "structs" },
+            (Type::Bool, Type::Bool) => "bools",
+            _ if l.is_number() && r.is_number() => return,
+            _ => {
+                self.error(loc, &format!("can not compare {} {} {}", l, op, r));
+                return;
+            },
+        };
+
+        if !equality {
+            self.error(loc, &format!("{} can not compare {}", op, kind));
+        }
+    }
+
+    // The rules of arith, on types.
+    fn arith(&mut self, op: BinOp, l: &Type, r: &Type, loc: &Location) -> Option<Type> {
+        if l.is_int() && r.is_int() {
+            match Type::meet_int(l, r) {
+                Some(t) => return Some(t),
+                None => {
+                    let err = format!("no integer type holds both {} and {}, {} {} {} needs operands of the same sign, cast one of them with as", l, r, l, op, r);
+                    self.error(loc, &err);
+                    return None;
+                }
+            }
+        }
+
+        if l.is_number() && r.is_number() {
+            // An int meets a float as that float, f32 and f64 meet at f64.
+            return Some(if *l == Type::F64 || *r == Type::F64 { Type::F64 } else { Type::F32 });
+        }
+
+        if *l == Type::Str && *r == Type::Str && op == BinOp::Add {
+            return Some(Type::Str);
+        }
+
+        self.error(loc, &format!("unsupported operand types {} {} {}", l, op, r));
+        return None;
+    }
+
+    // The casts cast knows how to make, whether the value survives is up to the value.
+    fn cast(&mut self, from: &Type, target: &Type, loc: &Location) -> Option<Type> {
+        let possible = match target {
+            _ if from == target => true,
+            _ if *from == Type::Str => target.is_int() || target.is_float() || *target == Type::Bool,
+            Type::Str => true,
+            t if t.is_int()   => *from == Type::Bool || from.is_number(),
+            t if t.is_float() => from.is_number(),
+            _ => false,
+        };
+
+        if !possible {
+            self.error(loc, &format!("can not cast {} to {}", from, target));
+            return None;
+        }
+
+        return Some(target.clone());
+    }
+
+    fn position(&mut self, expr: &Expr) {
+        if let Some(t) = self.expr(expr, None) {
+            if !t.is_int() {
+                self.error(&expr.loc, &format!("expected an integer position but found {}", t));
+            }
+        }
+    }
+
+    // t[index], a map takes a key, a string or an array a position.
+    fn index(&mut self, t: &Type, index: &Expr, loc: &Location) -> Option<Type> {
+        if let Type::Map(k, v) = t {
+            self.expect(index, k);
+            return Some((**v).clone());
+        }
+
+        self.position(index);
+
+        match t {
+            Type::Str      => return Some(Type::Str),
+            Type::Array(t) => return Some((**t).clone()),
+            _ => {
+                self.error(loc, &format!("can not index {}", t));
+                return None;
+            }
+        }
+    }
+
+    fn field(&mut self, t: &Type, name: &str, loc: &Location) -> Option<Type> {
+        if let Type::Named(s) = t {
+            if let Some(decl) = self.ev.structs.get(s) {
+                if let Some(field) = decl.fields.iter().find(|f| f.name == name) {
+                    return Some(field.declared_type.clone());
+                }
+            }
+        }
+
+        self.error(loc, &format!("{} has no field {}", t, name));
+        return None;
+    }
+
+    // What an assignment, push, pop or remove writes to: a variable, or an element or a field of one.
+    fn place(&mut self, expr: &Expr) -> Option<Type> {
+        match &expr.kind {
+            ExprKind::Var(name) => {
+                match self.lookup(name) {
+                    Some(entry) if entry.constant => {
+                        let err = self.changed_const(name, entry.loc);
+                        self.error(&expr.loc, &err);
+                        return None;
+                    },
+                    Some(entry) => return entry.t.clone(),
+                    None => {
+                        self.undefined(name, &expr.loc);
+                        return None;
+                    }
+                }
+            },
+            ExprKind::Index { target, index } => {
+                let t = self.place(target)?;
+
+                if t == Type::Str {
+                    self.error(&expr.loc, "a string does not change in place, build a new one with + or replace");
+                    return None;
+                }
+
+                return self.index(&t, index, &expr.loc);
+            },
+            ExprKind::Field { target, name } => {
+                let t = self.place(target)?;
+                return self.field(&t, name, &expr.loc);
+            },
+            _ => {
+                self.error(&expr.loc, "expected a variable or an element of one");
+                return None;
+            }
+        }
+    }
+
+    // The variable under xs[i].x.
+    fn root(expr: &Expr) -> &str {
+        match &expr.kind {
+            ExprKind::Index { target, .. } | ExprKind::Field { target, .. } => return Self::root(target),
+            ExprKind::Var(name) => return name,
+            _                   => return "",
+        }
+    }
+
+    fn assign_to(&mut self, target: &Expr, value: &Expr) {
+        let slot_type = match self.place(target) {
+            Some(t) => t,
+            None => {
+                self.expr(value, None);
+                return;
+            }
+        };
+
+        if let Some(found) = self.expr(value, Some(&slot_type)) {
+            if !Self::assignable(&found, &slot_type) {
+                let slot = match &target.kind {
+                    ExprKind::Field { name, .. } => format!("field {} of {}", name, Self::root(target)),
+                    _                            => format!("an element of {}", Self::root(target)),
+                };
+
+                let err = format!("can not assign {} to {}, it holds {}", found, slot, slot_type);
+                self.error(&value.loc, &err);
+            }
+        }
+    }
+
+    fn array(&mut self, elements: &[Expr], hint: Option<&Type>, loc: &Location) -> Option<Type> {
+        let mut element_type: Option<Type> = match hint {
+            Some(Type::Array(t)) => Some((**t).clone()),
+            _                    => None,
+        };
+
+        for element in elements {
+            match &element_type {
+                Some(t) => {
+                    let t = t.clone();
+                    self.expect(element, &t);
+                },
+                None => element_type = self.expr(element, None),
+            }
+        }
+
+        if elements.is_empty() && element_type.is_none() {
+            self.error(loc, "the type of an empty array is not known here, define it with -> [T] first");
+        }
+
+        return Some(Type::Array(Box::new(element_type?)));
+    }
+
+    // [v; n], n is known before the program runs and is not negative.
+    fn repeat(&mut self, value: &Expr, count: &Expr, hint: Option<&Type>) -> Option<Type> {
+        let errors = self.errors.len();
+        let copies = self.copies;
+
+        if let Some(t) = self.expr(count, None) {
+            if !t.is_int() {
+                self.error(&count.loc, &format!("expected an integer size but found {}", t));
+            }
+        }
+
+        if self.errors.len() == errors {
+            match self.fold(count, None) {
+                Ok(Some(n)) => {
+                    // [[v; n]; m] makes n * m elements, the cap is on all of them.
+                    match n.as_i128() {
+                        Some(i) if i < 0 => self.error(&count.loc, &format!("the size of an array can not be negative, found {}", n)),
+                        Some(i) if i <= MAX_REPEAT / copies => self.copies = copies * i.max(1),
+                        _ if copies > 1 => {
+                            let err = format!("an array can not hold {} elements {} times over, nested [v; n] make at most {} in all", n, copies, MAX_REPEAT);
+                            self.error(&count.loc, &err);
+                        },
+                        _ => {
+                            let err = format!("an array can not hold {} elements, [v; n] makes at most {}", n, MAX_REPEAT);
+                            self.error(&count.loc, &err);
+                        },
+                    }
+                },
+                Ok(None) => self.error(&count.loc, "the size of an array must be known before the program runs, use a number or a const"),
+                Err(e)   => self.report(e, &count.loc),
+            }
+        }
+
+        let element_type = match hint {
+            Some(Type::Array(t)) => {
+                self.expect(value, t);
+                Some((**t).clone())
+            },
+            _ => self.expr(value, None),
+        };
+
+        self.copies = copies;
+        return Some(Type::Array(Box::new(element_type?)));
+    }
+
+    fn map(&mut self, entries: &[(Expr, Expr)], hint: Option<&Type>, loc: &Location) -> Option<Type> {
+        let mut types: Option<(Type, Type)> = match hint {
+            Some(Type::Map(k, v)) => Some(((**k).clone(), (**v).clone())),
+            _                     => None,
+        };
+
+        for (key, value) in entries {
+            match &types {
+                Some((k, v)) => {
+                    let (k, v) = (k.clone(), v.clone());
+                    self.expect(key, &k);
+                    self.expect(value, &v);
+                },
+                None => {
+                    let k = self.expr(key, None);
+                    let v = self.expr(value, None);
+
+                    if let Some(k) = &k {
+                        if !k.is_key() {
+                            let err = format!("a map key can be an integer, a string or a bool, not {}", k);
+                            self.error(&key.loc, &err);
+                            return None;
+                        }
+                    }
+
+                    types = k.zip(v);
+                },
+            }
+        }
+
+        if entries.is_empty() && types.is_none() {
+            self.error(loc, "the type of an empty map is not known here, define it with -> map[K, V] first");
+        }
+
+        let (k, v) = types?;
+        return Some(Type::Map(Box::new(k), Box::new(v)));
+    }
+
+    fn struct_lit(&mut self, name: &str, fields: &[(String, Expr)], loc: &Location) -> Option<Type> {
+        let decl: &'a StructDecl = match self.ev.structs.get(name) {
+            Some(decl) => decl,
+            None => {
+                let err = format!("unknown struct {}, declare it with struct {} {{ ... }}", name, name);
+                self.error(loc, &err);
+                return None;
+            }
+        };
+
+        let mut given: Vec<&str> = Vec::new();
+
+        for (field, expr) in fields {
+            let declared = match decl.fields.iter().find(|f| f.name == *field) {
+                Some(declared) => declared,
+                None => {
+                    self.error(&expr.loc, &format!("{} has no field {}", name, field));
+                    self.expr(expr, None);
+                    continue;
+                }
+            };
+
+            if given.contains(&field.as_str()) {
+                self.error(&expr.loc, &format!("field {} of {} is given twice", field, name));
+            }
+
+            given.push(field);
+
+            if let Some(found) = self.expr(expr, Some(&declared.declared_type)) {
+                if !Self::assignable(&found, &declared.declared_type) {
+                    let err = format!("field {} of {} holds {} but found {}", field, name, declared.declared_type, found);
+                    self.error(&expr.loc, &err);
+                }
+            }
+        }
+
+        for f in &decl.fields {
+            if !given.contains(&f.name.as_str()) {
+                self.error(loc, &format!("{} needs a value for field {}", name, f.name));
+            }
+        }
+
+        return Some(Type::Named(name.to_string()));
+    }
+
+    fn construct(&mut self, name: &str, args: &[Expr], loc: &Location) -> Option<Type> {
+        let decl: &'a EnumDecl = self.ev.variants[name];
+        let variant = decl.variants.iter().find(|v| v.name == name)?;
+
+        if args.len() != variant.fields.len() {
+            let err = format!("{} holds {} values but got {}", name, variant.fields.len(), args.len());
+            self.error(loc, &err);
+            return None;
+        }
+
+        for (i, (arg, t)) in args.iter().zip(&variant.fields).enumerate() {
+            if let Some(found) = self.expr(arg, Some(t)) {
+                if !Self::assignable(&found, t) {
+                    let err = format!("value {} of {} expects {} but found {}", i + 1, name, t, found);
+                    self.error(&arg.loc, &err);
+                }
+            }
+        }
+
+        return Some(Type::Named(decl.name.clone()));
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], loc: &Location) -> Ret {
+        if BUILTINS.contains(&name) {
+            return self.builtin(name, args, loc);
+        }
+
+        let proc: &'a Proc = match self.ev.procs.get(name) {
+            Some(proc) => proc,
+            None if self.ev.variants.contains_key(name) => {
+                match self.construct(name, args, loc) {
+                    Some(t) => return Ret::Value(t),
+                    None    => return Ret::Unknown,
+                }
+            },
+            None => {
+                self.error(loc, &format!("{} is not defined", name));
+                return Ret::Unknown;
+            }
+        };
+
+        if args.len() != proc.params.len() {
+            let err = format!("{} expects {} arguments but got {}", name, proc.params.len(), args.len());
+            self.error(loc, &err);
+        } else {
+            for (i, (arg, param)) in args.iter().zip(&proc.params).enumerate() {
+                if let Some(found) = self.expr(arg, Some(&param.declared_type)) {
+                    if !Self::assignable(&found, &param.declared_type) {
+                        let err = format!("argument {} of {} expects {} but found {}", i + 1, name, param.declared_type, found);
+                        self.error(&arg.loc, &err);
+                    }
+                }
+            }
+        }
+
+        match &proc.return_type {
+            Some(t) => return Ret::Value(t.clone()),
+            None    => return Ret::Nothing,
+        }
+    }
+
+    // The signatures of the built-ins, as call_builtin checks them.
+    fn builtin(&mut self, name: &str, args: &[Expr], loc: &Location) -> Ret {
+        if name == WRITE || name == WRITELN {
+            for arg in args {
+                self.expr(arg, None);
+            }
+
+            return Ret::Nothing;
+        }
+
+        let arity = match name {
+            LEN | UPPER | LOWER | TRIM | KEYS | VALUES | POP => 1,
+            SPLIT | CONTAINS | FIND | REPEAT | PUSH | REMOVE => 2,
+            REPLACE                                          => 3,
+            _ => unreachable!("{} is not a built-in", name),
+        };
+
+        if args.len() != arity {
+            let err = format!("{} expects {} arguments but got {}", name, arity, args.len());
+            self.error(loc, &err);
+            return Ret::Unknown;
+        }
+
+        if name == PUSH || name == POP || name == REMOVE {
+            return self.container(name, args);
+        }
+
+        let mut types: Vec<Type> = Vec::new();
+
+        for arg in args {
+            match self.expr(arg, None) {
+                Some(t) => types.push(t),
+                None    => return Ret::Unknown,
+            }
+        }
+
+        let result = match name {
+            LEN => {
+                match &types[0] {
+                    Type::Str | Type::Array(_) | Type::Map(..) => Type::I32,
+                    _ => return self.arg_error(name, args, &types, 0, "a string, an array or a map"),
+                }
+            },
+            KEYS | VALUES => {
+                match &types[0] {
+                    Type::Map(k, _) if name == KEYS => Type::Array(k.clone()),
+                    Type::Map(_, v)                 => Type::Array(v.clone()),
+                    _ => return self.arg_error(name, args, &types, 0, "a map"),
+                }
+            },
+            CONTAINS if matches!(types[0], Type::Array(_) | Type::Map(..)) => {
+                let t = match &types[0] {
+                    Type::Array(t) | Type::Map(t, _) => t,
+                    _ => unreachable!(),
+                };
+
+                if !Self::assignable(&types[1], t) {
+                    let err = Self::mismatch(&types[1], t);
+                    self.error(&args[1].loc, &err);
+                }
+
+                Type::Bool
+            },
+            REPEAT if !types[1].is_int() => return self.arg_error(name, args, &types, 1, INT),
+            _ => {
+                // The rest of the string library takes strings, repeat takes an int after its string.
+                let strings = if name == REPEAT { 1 } else { arity };
+
+                for i in 0..strings {
+                    if types[i] != Type::Str {
+                        return self.arg_error(name, args, &types, i, STRING);
+                    }
+                }
+
+                match name {
+                    CONTAINS => Type::Bool,
+                    FIND     => Type::I32,
+                    SPLIT    => Type::Array(Box::new(Type::Str)),
+                    _        => Type::Str,
+                }
+            },
+        };
+
+        return Ret::Value(result);
+    }
+
+    fn arg_error(&mut self, name: &str, args: &[Expr], types: &[Type], i: usize, expected: &str) -> Ret {
+        let err = format!("argument {} of {} expects {} but found {}", i + 1, name, expected, types[i]);
+        self.error(&args[i].loc, &err);
+        return Ret::Unknown;
+    }
+
+    // push(xs, v), pop(xs) and remove(m, k) change their first argument in place.
+    fn container(&mut self, name: &str, args: &[Expr]) -> Ret {
+        let t = match self.place(&args[0]) {
+            Some(t) => t,
+            None    => return Ret::Unknown,
+        };
+
+        match (name, t) {
+            (PUSH, Type::Array(element)) => {
+                self.expect(&args[1], &element);
+                return Ret::Nothing;
+            },
+            (POP, Type::Array(element)) => return Ret::Value(*element),
+            (REMOVE, Type::Map(k, v)) => {
+                self.expect(&args[1], &k);
+                return Ret::Value(*v);
+            },
+            (_, t) => {
+                let expected = if name == REMOVE { "a map" } else { "an array" };
+                return self.arg_error(name, args, &[t], 0, expected);
+            }
+        }
+    }
+
+    // Binds the payload a pattern names, and checks a value pattern against the subject.
+    fn bind(&mut self, arm: &Arm, subject: &Type) {
+        match &arm.pattern {
+            Pattern::Wildcard => {},
+            Pattern::Literal(expr) => {
+                if let Some(t) = self.expr(expr, Some(subject)) {
+                    // A variant pattern was expected on an enum, check_exhaustive said so.
+                    if !matches!(subject, Type::Named(_)) {
+                        self.compare(BinOp::Eq, subject, &t, &expr.loc);
+                    }
+                }
+            },
+            Pattern::Variant { name, bindings } => {
+                let variant = match subject {
+                    Type::Named(e) => self.ev.enums.get(e).and_then(|decl| decl.variants.iter().find(|v| v.name == *name)),
+                    _              => None,
+                };
+
+                for (i, binding) in bindings.iter().enumerate() {
+                    if binding != WILDCARD {
+                        let t = variant.and_then(|v| v.fields.get(i)).cloned();
+                        self.define(binding, t, arm.loc);
+                    }
+                }
+            },
+        }
+    }
+
+    fn match_stmt(&mut self, m: &Match, loc: &Location) {
+        let subject = self.expr(&m.subject, None);
+
+        if let Some(t) = &subject {
+            if let Err(e) = self.ev.check_exhaustive(m, t, loc) {
+                self.report(e, loc);
+            }
+        }
+
+        for arm in &m.arms {
+            self.push_scope();
+
+            if let Some(t) = &subject {
+                self.bind(arm, t);
+            }
+
+            let end = match &arm.body {
+                ArmBody::Block(block) => {
+                    self.block(block);
+                    block.end
+                },
+                ArmBody::Expr(expr) => {
+                    self.expr_stmt(expr);
+                    expr.loc
+                },
+            };
+
+            self.pop_scope(end);
+        }
+    }
+
+    // The arms of a match expression agree on a type, numbers meet the way they do in arithmetic.
+    fn match_expr(&mut self, m: &Match, hint: Option<&Type>, loc: &Location) -> Option<Type> {
+        let subject = self.expr(&m.subject, None)?;
+
+        if let Err(e) = self.ev.check_exhaustive(m, &subject, loc) {
+            self.report(e, loc);
+        }
+
+        let mut result: Option<Type> = None;
+
+        for arm in &m.arms {
+            let expr = match &arm.body {
+                ArmBody::Expr(expr) => expr,
+                ArmBody::Block(_)   => unreachable!("the parser only gives a match expression expression arms"),
+            };
+
+            self.push_scope();
+            self.bind(arm, &subject);
+            let t = self.expr(expr, hint);
+            self.pop_scope(expr.loc);
+
+            result = match (result, t) {
+                (Some(r), Some(t)) if r == t => Some(r),
+                (Some(r), Some(t)) if r.is_int() && t.is_int() && Type::meet_int(&r, &t).is_some() => Type::meet_int(&r, &t),
+                (Some(r), Some(t)) if r.is_number() && t.is_number() => {
+                    Some(if r == Type::F64 || t == Type::F64 { Type::F64 } else { Type::F32 })
+                },
+                (Some(r), Some(t)) => {
+                    self.error(&expr.loc, &format!("this arm gives {} but the arms above give {}", t, r));
+                    Some(r)
+                },
+                (r, t) => r.or(t),
+            };
+        }
+
+        return result;
+    }
+}
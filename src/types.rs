@@ -0,0 +1,166 @@
+// Types, what a variable was declared as.
+use std::fmt;
+use crate::enums::*;
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum Type {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
+    Bool,
+    Str,
+    Array(Box<Type>), // [T]
+    Map(Box<Type>, Box<Type>), // map[K, V]
+    Named(String), // Declared with struct or enum, by name.
+}
+
+impl Type {
+    pub fn from_token(t: TokenT) -> Option<Type> {
+        return match t {
+            TokenT::INT_T_8    => Some(Type::I8),
+            TokenT::INT_T_16   => Some(Type::I16),
+            TokenT::INT_T      => Some(Type::I32),
+            TokenT::INT_T_64   => Some(Type::I64),
+            TokenT::INT_T_128  => Some(Type::I128),
+            TokenT::UINT_T_8   => Some(Type::U8),
+            TokenT::UINT_T_16  => Some(Type::U16),
+            TokenT::UINT_T_32  => Some(Type::U32),
+            TokenT::UINT_T_64  => Some(Type::U64),
+            TokenT::UINT_T_128 => Some(Type::U128),
+            TokenT::FLOAT_T    => Some(Type::F64),
+            TokenT::FLOAT_T_32 => Some(Type::F32),
+            TokenT::STRING_T   => Some(Type::Str),
+            TokenT::BOOL_T     => Some(Type::Bool),
+            _                  => None,
+        };
+    }
+
+    pub fn is_int(&self) -> bool {
+        return self.is_signed() || self.is_unsigned();
+    }
+
+    pub fn is_signed(&self) -> bool {
+        return matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::I128);
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        return matches!(self, Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::U128);
+    }
+
+    pub fn is_float(&self) -> bool {
+        return matches!(self, Type::F32 | Type::F64);
+    }
+
+    pub fn is_number(&self) -> bool {
+        return self.is_int() || self.is_float();
+    }
+
+    // The article read before the name of the type, an int but a string.
+    pub fn article(&self) -> &'static str {
+        let name      = self.to_string();
+        let mut chars = name.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some('a' | 'e' | 'i' | 'o' | 'A' | 'E' | 'I' | 'O'), _) => return "an",
+            (Some('f'), Some(c)) if c.is_ascii_digit()               => return "an",
+            _                                                        => return "a",
+        }
+    }
+
+    // Keys are compared for equality, a float would not find itself again after rounding.
+    pub fn is_key(&self) -> bool {
+        return self.is_int() || matches!(self, Type::Str | Type::Bool);
+    }
+
+    // Smallest and largest value of an integer type, u128 is past i128 and has its own arithmetic.
+    pub fn int_min(&self) -> i128 {
+        return match self {
+            Type::I8   => i8::MIN as i128,
+            Type::I16  => i16::MIN as i128,
+            Type::I32  => i32::MIN as i128,
+            Type::I64  => i64::MIN as i128,
+            _ if self.is_unsigned() => 0,
+            _          => i128::MIN,
+        };
+    }
+
+    pub fn int_max(&self) -> i128 {
+        return match self {
+            Type::I8   => i8::MAX as i128,
+            Type::I16  => i16::MAX as i128,
+            Type::I32  => i32::MAX as i128,
+            Type::I64  => i64::MAX as i128,
+            Type::U8   => u8::MAX as i128,
+            Type::U16  => u16::MAX as i128,
+            Type::U32  => u32::MAX as i128,
+            Type::U64  => u64::MAX as i128,
+            _          => i128::MAX,
+        };
+    }
+
+    // The integer type two operands meet at, the wider one when they have the same sign,
+    // the smallest signed type that holds both when they do not. Nothing holds a u128 and a signed type.
+    pub fn meet_int(a: &Type, b: &Type) -> Option<Type> {
+        if a.is_unsigned() == b.is_unsigned() {
+            return Some(if a.bits() >= b.bits() { a.clone() } else { b.clone() });
+        }
+
+        let (signed, unsigned) = if a.is_unsigned() { (b, a) } else { (a, b) };
+
+        let signed_types = [Type::I8, Type::I16, Type::I32, Type::I64, Type::I128];
+        return signed_types.into_iter().find(|t| t.bits() >= signed.bits() && t.bits() > unsigned.bits());
+    }
+
+    // Width of an integer type, 0 for anything else.
+    pub fn bits(&self) -> u32 {
+        return match self {
+            Type::I8   => 8,
+            Type::I16  => 16,
+            Type::I32  => 32,
+            Type::I64  => 64,
+            Type::I128 => 128,
+            Type::U8   => 8,
+            Type::U16  => 16,
+            Type::U32  => 32,
+            Type::U64  => 64,
+            Type::U128 => 128,
+            _          => 0,
+        };
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match self {
+            Type::I8   => INT_8,
+            Type::I16  => INT_16,
+            Type::I32  => INT,
+            Type::I64  => INT_64,
+            Type::I128 => INT_128,
+            Type::U8   => UINT_8,
+            Type::U16  => UINT_16,
+            Type::U32  => UINT_32,
+            Type::U64  => UINT_64,
+            Type::U128 => UINT_128,
+            Type::F32  => FLOAT_32,
+            Type::F64  => FLOAT,
+            Type::Bool => BOOL,
+            Type::Str  => STRING,
+            Type::Array(t) => return write!(f, "[{}]", t),
+            Type::Map(k, v) => return write!(f, "{}[{}, {}]", MAP, k, v),
+            Type::Named(name) => return write!(f, "{}", name),
+        };
+
+        return write!(f, "{}", printable)
+    }
+}
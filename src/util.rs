@@ -1,8 +1,16 @@
 // Utility functions.
 
 use std::io;
+use crate::token::Location;
+
 pub fn make_error(text: &str) -> io::Error { 
-    return io::Error::new(io::ErrorKind::Other, text);    
+    return io::Error::other(text);    
+}
+
+// Formats an error as file:row:col text.
+pub fn make_error_at(file_path: &str, loc: &Location, text: &str) -> io::Error {
+    let err = format!("{}:{}:{} {}", file_path, loc.row, loc.col, text);
+    return make_error(&err);
 }
 
 /*
@@ -0,0 +1,317 @@
+// Runtime values.
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::collections::HashMap;
+use crate::enums::{ BOOL_TRUE, BOOL_FALSE };
+use crate::types::Type;
+
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum Value {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    Array(Type, Vec<Value>), // The type of the elements and the elements.
+    Map(Type, Type, Entries), // The key and value types and the entries.
+    Struct(String, Vec<(String, Value)>), // The name of the struct and its fields, in declaration order.
+    Enum(String, String, Vec<Value>), // The name of the enum, the variant and its payload.
+}
+
+impl Value {
+    // The value a variable holds when it is defined without one.
+    pub fn default_for(t: &Type) -> Value {
+        return match t {
+            Type::I8   => Value::I8(0),
+            Type::I16  => Value::I16(0),
+            Type::I32  => Value::I32(0),
+            Type::I64  => Value::I64(0),
+            Type::I128 => Value::I128(0),
+            Type::U8   => Value::U8(0),
+            Type::U16  => Value::U16(0),
+            Type::U32  => Value::U32(0),
+            Type::U64  => Value::U64(0),
+            Type::U128 => Value::U128(0),
+            Type::F32  => Value::F32(0.0),
+            Type::F64  => Value::F64(0.0),
+            Type::Bool => Value::Bool(true),
+            Type::Str  => Value::Str(String::new()),
+            Type::Array(t) => Value::Array((**t).clone(), Vec::new()),
+            Type::Map(k, v) => Value::Map((**k).clone(), (**v).clone(), Entries::new()),
+            Type::Named(_) => unreachable!("{} is built from its declaration by the evaluator", t),
+        };
+    }
+
+    // An integer of the given type, None when it does not fit.
+    pub fn int_from_i128(v: i128, t: &Type) -> Option<Value> {
+        return match t {
+            Type::I8   => i8::try_from(v).ok().map(Value::I8),
+            Type::I16  => i16::try_from(v).ok().map(Value::I16),
+            Type::I32  => i32::try_from(v).ok().map(Value::I32),
+            Type::I64  => i64::try_from(v).ok().map(Value::I64),
+            Type::I128 => Some(Value::I128(v)),
+            Type::U8   => u8::try_from(v).ok().map(Value::U8),
+            Type::U16  => u16::try_from(v).ok().map(Value::U16),
+            Type::U32  => u32::try_from(v).ok().map(Value::U32),
+            Type::U64  => u64::try_from(v).ok().map(Value::U64),
+            Type::U128 => u128::try_from(v).ok().map(Value::U128),
+            _          => None,
+        };
+    }
+
+    // Keeps the low bits of v, two's complement wrapping into the given type.
+    pub fn int_wrapping(v: i128, t: &Type) -> Option<Value> {
+        return match t {
+            Type::I8   => Some(Value::I8(v as i8)),
+            Type::I16  => Some(Value::I16(v as i16)),
+            Type::I32  => Some(Value::I32(v as i32)),
+            Type::I64  => Some(Value::I64(v as i64)),
+            Type::I128 => Some(Value::I128(v)),
+            Type::U8   => Some(Value::U8(v as u8)),
+            Type::U16  => Some(Value::U16(v as u16)),
+            Type::U32  => Some(Value::U32(v as u32)),
+            Type::U64  => Some(Value::U64(v as u64)),
+            Type::U128 => Some(Value::U128(v as u128)),
+            _          => None,
+        };
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        return match self {
+            Value::I8(v)   => Some(*v as i128),
+            Value::I16(v)  => Some(*v as i128),
+            Value::I32(v)  => Some(*v as i128),
+            Value::I64(v)  => Some(*v as i128),
+            Value::I128(v) => Some(*v),
+            Value::U8(v)   => Some(*v as i128),
+            Value::U16(v)  => Some(*v as i128),
+            Value::U32(v)  => Some(*v as i128),
+            Value::U64(v)  => Some(*v as i128),
+            Value::U128(v) => i128::try_from(*v).ok(),
+            _              => None,
+        };
+    }
+
+    // Any integer that is not negative.
+    pub fn as_u128(&self) -> Option<u128> {
+        if let Value::U128(v) = self {
+            return Some(*v);
+        }
+
+        return self.as_i128().and_then(|v| u128::try_from(v).ok());
+    }
+
+    // A float of the given type, f32 rounds to the nearest f32.
+    pub fn float_from_f64(v: f64, t: &Type) -> Option<Value> {
+        return match t {
+            Type::F32 => Some(Value::F32(v as f32)),
+            Type::F64 => Some(Value::F64(v)),
+            _         => None,
+        };
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        return match self {
+            Value::I8(v)   => Some(*v as f64),
+            Value::I16(v)  => Some(*v as f64),
+            Value::I32(v)  => Some(*v as f64),
+            Value::I64(v)  => Some(*v as f64),
+            Value::I128(v) => Some(*v as f64),
+            Value::U8(v)   => Some(*v as f64),
+            Value::U16(v)  => Some(*v as f64),
+            Value::U32(v)  => Some(*v as f64),
+            Value::U64(v)  => Some(*v as f64),
+            Value::U128(v) => Some(*v as f64),
+            Value::F32(v)  => Some(*v as f64),
+            Value::F64(v)  => Some(*v),
+            _              => None,
+        };
+    }
+
+    // How a value shows inside an array or a map, strings are quoted so ["a, b"] is not ["a", "b"].
+    pub fn quoted(&self) -> String {
+        if let Value::Str(s) = self {
+            return format!("\"{}\"", s);
+        }
+
+        return self.to_string();
+    }
+
+    // == in the language, maps are equal when they hold the same entries in any order.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Array(a, xs), Value::Array(b, ys)) => {
+                return a == b && xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.equals(y));
+            },
+            (Value::Struct(a, xs), Value::Struct(b, ys)) => {
+                return a == b && xs.iter().zip(ys).all(|((_, x), (_, y))| x.equals(y));
+            },
+            (Value::Enum(a, v, xs), Value::Enum(b, w, ys)) => {
+                return a == b && v == w && xs.iter().zip(ys).all(|(x, y)| x.equals(y));
+            },
+            (Value::Map(ak, av, xs), Value::Map(bk, bv, ys)) => {
+                let same = |(k, v): &(Value, Value)| ys.get(k).is_some_and(|w| v.equals(w));
+                return ak == bk && av == bv && xs.len() == ys.len() && xs.iter().all(same);
+            },
+            _ => return self == other,
+        }
+    }
+
+    pub fn get_type(&self) -> Type {
+        return match self {
+            Value::I8(_)   => Type::I8,
+            Value::I16(_)  => Type::I16,
+            Value::I32(_)  => Type::I32,
+            Value::I64(_)  => Type::I64,
+            Value::I128(_) => Type::I128,
+            Value::U8(_)   => Type::U8,
+            Value::U16(_)  => Type::U16,
+            Value::U32(_)  => Type::U32,
+            Value::U64(_)  => Type::U64,
+            Value::U128(_) => Type::U128,
+            Value::F32(_)  => Type::F32,
+            Value::F64(_)  => Type::F64,
+            Value::Bool(_) => Type::Bool,
+            Value::Str(_)  => Type::Str,
+            Value::Array(t, _) => Type::Array(Box::new(t.clone())),
+            Value::Map(k, v, _) => Type::Map(Box::new(k.clone()), Box::new(v.clone())),
+            Value::Struct(name, _) => Type::Named(name.clone()),
+            Value::Enum(name, _, _) => Type::Named(name.clone()),
+        };
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::I8(v)   => write!(f, "{}", v),
+            Value::I16(v)  => write!(f, "{}", v),
+            Value::I32(v)  => write!(f, "{}", v),
+            Value::I64(v)  => write!(f, "{}", v),
+            Value::I128(v) => write!(f, "{}", v),
+            Value::U8(v)   => write!(f, "{}", v),
+            Value::U16(v)  => write!(f, "{}", v),
+            Value::U32(v)  => write!(f, "{}", v),
+            Value::U64(v)  => write!(f, "{}", v),
+            Value::U128(v) => write!(f, "{}", v),
+            // Floats always show they are floats, 2.0 and not 2.
+            Value::F32(v)  => write!(f, "{:?}", v),
+            Value::F64(v)  => write!(f, "{:?}", v),
+            Value::Bool(v) => write!(f, "{}", if *v { BOOL_TRUE } else { BOOL_FALSE }),
+            Value::Str(v)  => write!(f, "{}", v),
+            Value::Array(_, items) => {
+                let items: Vec<String> = items.iter().map(|item| item.quoted()).collect();
+                write!(f, "[{}]", items.join(", "))
+            },
+            Value::Map(_, _, entries) => {
+                let entries: Vec<String> = entries.iter().map(|(k, v)| format!("{}: {}", k.quoted(), v.quoted())).collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            },
+            Value::Struct(name, fields) => {
+                let fields: Vec<String> = fields.iter().map(|(n, v)| format!("{}: {}", n, v.quoted())).collect();
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            },
+            Value::Enum(_, variant, payload) if payload.is_empty() => write!(f, "{}", variant),
+            Value::Enum(_, variant, payload) => {
+                let payload: Vec<String> = payload.iter().map(|v| v.quoted()).collect();
+                write!(f, "{}({})", variant, payload.join(", "))
+            },
+        }
+    }
+}
+
+// Map keys are ints, strings and bools, for those == is an equivalence. A float key never gets this far.
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+
+        match self {
+            Value::Str(s)  => s.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::U128(v) => v.hash(state),
+            Value::F32(v)  => v.to_bits().hash(state),
+            Value::F64(v)  => v.to_bits().hash(state),
+            _ => {
+                if let Some(i) = self.as_i128() {
+                    i.hash(state);
+                }
+            },
+        }
+    }
+}
+
+// The entries of a map in insertion order, with the slot of every key so a lookup does not scan them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Entries {
+    items: Vec<(Value, Value)>,
+    slots: HashMap<Value, usize>,
+}
+
+impl Entries {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (Value, Value)> {
+        return self.items.iter();
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        return self.slots.get(key).map(|&i| &self.items[i].1);
+    }
+
+    pub fn get_mut(&mut self, key: &Value) -> Option<&mut Value> {
+        return self.slots.get(key).map(|&i| &mut self.items[i].1);
+    }
+
+    // A key that is there keeps its place, a new one goes last.
+    pub fn insert(&mut self, key: Value, value: Value) {
+        match self.slots.get(&key) {
+            Some(&i) => self.items[i].1 = value,
+            None => {
+                self.slots.insert(key.clone(), self.items.len());
+                self.items.push((key, value));
+            }
+        }
+    }
+
+    // The keys after it move up a slot, so they keep their order.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let i = self.slots.remove(key)?;
+        let (_, value) = self.items.remove(i);
+
+        for (k, _) in &self.items[i..] {
+            if let Some(slot) = self.slots.get_mut(k) {
+                *slot -= 1;
+            }
+        }
+
+        return Some(value);
+    }
+}
+
+impl IntoIterator for Entries {
+    type Item     = (Value, Value);
+    type IntoIter = std::vec::IntoIter<(Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.items.into_iter();
+    }
+}